@@ -19,12 +19,18 @@ _pack() {
             config)
                 cmd+="__config"
                 ;;
+            export)
+                cmd+="__export"
+                ;;
             generate)
                 cmd+="__generate"
                 ;;
             help)
                 cmd+="__help"
                 ;;
+            import)
+                cmd+="__import"
+                ;;
             install)
                 cmd+="__install"
                 ;;
@@ -34,6 +40,12 @@ _pack() {
             move)
                 cmd+="__move"
                 ;;
+            outdated)
+                cmd+="__outdated"
+                ;;
+            retry)
+                cmd+="__retry"
+                ;;
             uninstall)
                 cmd+="__uninstall"
                 ;;
@@ -47,13 +59,41 @@ _pack() {
 
     case "${cmd}" in
         pack)
-            opts=" -h -V  --help --version   list install uninstall config move update generate completions help"
+            opts=" -h -V -P -t  --no-tty --help --version --profile --root --target --output --packfile   list install uninstall config move update outdated retry generate import export completions help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --root)
+                    COMPREPLY=("<DIR>")
+                    return 0
+                    ;;
+                --target)
+                    COMPREPLY=($(compgen -W "vim neovim" -- ${cur}))
+                    return 0
+                    ;;
+                    -t)
+                    COMPREPLY=($(compgen -W "vim neovim" -- ${cur}))
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
+                --packfile)
+                    COMPREPLY=("<FILE>")
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -63,13 +103,25 @@ _pack() {
             ;;
         
         pack__completions)
-            opts=" -h -V  --help --version  <SHELL> "
+            opts=" -h -V -P  --help --version --no-tty --profile --output  <SHELL> "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -78,13 +130,60 @@ _pack() {
             return 0
             ;;
         pack__config)
-            opts=" -d -h -V  --delete --help --version  <package> "
+            opts=" -d -a -h -V -P  --delete --after --lua --help --version --no-tty --profile --output  <package> "
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+                return 0
+            fi
+            case "${prev}" in
+                
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+            return 0
+            ;;
+        pack__export)
+            opts=" -h -V -f -P  --help --version --no-tty --format --profile --output  "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --format)
+                    COMPREPLY=($(compgen -W "vim-plug lua json" -- ${cur}))
+                    return 0
+                    ;;
+                    -f)
+                    COMPREPLY=($(compgen -W "vim-plug lua json" -- ${cur}))
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -93,13 +192,25 @@ _pack() {
             return 0
             ;;
         pack__generate)
-            opts=" -h -V  --help --version  "
+            opts=" -h -V -P  --help --version --no-tty --profile --output  "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -108,13 +219,68 @@ _pack() {
             return 0
             ;;
         pack__help)
-            opts=" -h -V  --help --version  "
+            opts=" -h -V -P  --help --version --no-tty --profile --output  "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+            return 0
+            ;;
+        pack__import)
+            opts=" -h -V -f -c -P  --help --version --no-tty --from --category --profile --output  <file> "
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+                return 0
+            fi
+            case "${prev}" in
+                
+                --from)
+                    COMPREPLY=($(compgen -W "vim-plug vundle dein packer lazy" -- ${cur}))
+                    return 0
+                    ;;
+                    -f)
+                    COMPREPLY=($(compgen -W "vim-plug vundle dein packer lazy" -- ${cur}))
+                    return 0
+                    ;;
+                --category)
+                    COMPREPLY=("<CATEGORY>")
+                    return 0
+                    ;;
+                    -c)
+                    COMPREPLY=("<CATEGORY>")
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -123,7 +289,7 @@ _pack() {
             return 0
             ;;
         pack__install)
-            opts=" -o -l -h -V -c -j  --opt --local --help --version --category --on --for --build --threads  <package>... "
+            opts=" -o -l -h -V -c -j -P  --opt --local --help --version --no-tty --category --on --for --keys --event --build --requires --threads --profile --output  <package>... "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
@@ -131,31 +297,55 @@ _pack() {
             case "${prev}" in
                 
                 --category)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<CATEGORY>")
                     return 0
                     ;;
                     -c)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<CATEGORY>")
                     return 0
                     ;;
                 --on)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<LOAD_CMD>")
                     return 0
                     ;;
                 --for)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<TYPES>")
+                    return 0
+                    ;;
+                --keys)
+                    COMPREPLY=("<MAPPINGS>")
+                    return 0
+                    ;;
+                --event)
+                    COMPREPLY=("<EVENT>...")
                     return 0
                     ;;
                 --build)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<BUILD_CMD>")
+                    return 0
+                    ;;
+                --requires)
+                    COMPREPLY=("<PACKAGES>")
                     return 0
                     ;;
                 --threads)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<THREADS>")
                     return 0
                     ;;
                     -j)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<THREADS>")
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
                     return 0
                     ;;
                 *)
@@ -166,7 +356,7 @@ _pack() {
             return 0
             ;;
         pack__list)
-            opts=" -s -o -h -V -c  --start --opt --help --version --category  "
+            opts=" -s -o -d -h -V -c -P  --start --opt --detached --help --version --no-tty --category --profile --output  "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
@@ -174,11 +364,23 @@ _pack() {
             case "${prev}" in
                 
                 --category)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<CATEGORY>")
                     return 0
                     ;;
                     -c)
-                    COMPREPLY=($(compgen -f ${cur}))
+                    COMPREPLY=("<CATEGORY>")
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
                     return 0
                     ;;
                 *)
@@ -189,13 +391,95 @@ _pack() {
             return 0
             ;;
         pack__move)
-            opts=" -o -h -V  --opt --help --version  <package> <category> "
+            opts=" -o -h -V -P  --opt --help --version --no-tty --profile --output  <package> <category> "
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+                return 0
+            fi
+            case "${prev}" in
+                
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+            return 0
+            ;;
+        pack__outdated)
+            opts=" -h -V -j -P  --help --version --no-tty --threads --profile --output  <package>... "
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+                return 0
+            fi
+            case "${prev}" in
+                
+                --threads)
+                    COMPREPLY=("<THREADS>")
+                    return 0
+                    ;;
+                    -j)
+                    COMPREPLY=("<THREADS>")
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
+            return 0
+            ;;
+        pack__retry)
+            opts=" -h -V -j -P  --help --version --no-tty --threads --profile --output  "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --threads)
+                    COMPREPLY=("<THREADS>")
+                    return 0
+                    ;;
+                    -j)
+                    COMPREPLY=("<THREADS>")
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -204,13 +488,25 @@ _pack() {
             return 0
             ;;
         pack__uninstall)
-            opts=" -a -h -V  --all --help --version  <package>... "
+            opts=" -a -h -V -P  --all --help --version --no-tty --profile --output  <package>... "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -219,13 +515,25 @@ _pack() {
             return 0
             ;;
         pack__update)
-            opts=" -s -p -j -h -V  --skip --packfile --threads --help --version  <package>... "
+            opts=" -s -p -j -h -V -P  --skip --packfile --threads --help --version --no-tty --profile --output  <package>... "
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- ${cur}) )
                 return 0
             fi
             case "${prev}" in
                 
+                --profile)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                    -P)
+                    COMPREPLY=("<PROFILE>")
+                    return 0
+                    ;;
+                --output)
+                    COMPREPLY=($(compgen -W "text json" -- ${cur}))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
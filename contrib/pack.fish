@@ -2,52 +2,134 @@ function __fish_pack_packages
     pack list | string split ' =>' --field 1
 end
 
-complete -c pack -s h -l help -d 'Prints help information'
-complete -c pack -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_use_subcommand" -f -a "list" -d 'List installed packages'
-complete -c pack -n "__fish_use_subcommand" -f -a "install" -d 'Install new packages/plugins'
-complete -c pack -n "__fish_use_subcommand" -f -a "uninstall" -d 'Uninstall packages/plugins'
-complete -c pack -n "__fish_use_subcommand" -f -a "config" -d 'Configure/edit the package specific configuration'
-complete -c pack -n "__fish_use_subcommand" -f -a "move" -d 'Move a package to a different category or make it optional.'
-complete -c pack -n "__fish_use_subcommand" -f -a "update" -d 'Update packages'
-complete -c pack -n "__fish_use_subcommand" -f -a "generate" -d 'Generate the pack package file'
-complete -c pack -n "__fish_use_subcommand" -f -a "completions" -d 'Generates completion scripts for your shell'
-complete -c pack -n "__fish_use_subcommand" -f -a "help" -d 'Prints this message or the help of the given subcommand(s)'
-complete -c pack -n "__fish_seen_subcommand_from list" -s c -l category -d 'List packages under this category'
-complete -c pack -n "__fish_seen_subcommand_from list" -s s -l start -d 'List start packages'
-complete -c pack -n "__fish_seen_subcommand_from list" -s o -l opt -d 'List optional packages'
-complete -c pack -n "__fish_seen_subcommand_from list" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from list" -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_seen_subcommand_from install" -s c -l category -d 'Install package under provided category'
-complete -c pack -n "__fish_seen_subcommand_from install" -l on -d 'Command for loading the plugins'
-complete -c pack -n "__fish_seen_subcommand_from install" -l for -d 'Load this plugins for specific types'
-complete -c pack -n "__fish_seen_subcommand_from install" -l build -d 'Build command for build package'
-complete -c pack -n "__fish_seen_subcommand_from install" -s j -l threads -d 'Installing packages concurrently'
-complete -c pack -n "__fish_seen_subcommand_from install" -s o -l opt -d 'Install plugins as opt(ional)'
-complete -c pack -n "__fish_seen_subcommand_from install" -s l -l local -d 'Install local plugins'
-complete -c pack -n "__fish_seen_subcommand_from install" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from install" -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_seen_subcommand_from uninstall" -s a -l all -d 'remove all package related configuration as well'
-complete -c pack -n "__fish_seen_subcommand_from uninstall" -s h -l help -d 'Prints help information'
+function __fish_using_command
+    set cmd (commandline -opc)
+    if [ (count $cmd) -eq (count $argv) ]
+        for i in (seq (count $argv))
+            if [ $cmd[$i] != $argv[$i] ]
+                return 1
+            end
+        end
+        return 0
+    end
+    return 1
+end
+
+complete -c pack -n "__fish_using_command pack" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack" -l root -d 'Vim config directory to operate on [env: VIM_CONFIG_PATH]'
+complete -c pack -n "__fish_using_command pack" -s t -l target -d 'Editors to manage packages for, detected by default [env: PACK_TARGET]' -r -f -a "vim neovim"
+complete -c pack -n "__fish_using_command pack" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack" -l packfile -d 'Packfile to use instead of the one of the profile [env: PACK_FILE]'
+complete -c pack -n "__fish_using_command pack" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack" -f -a "list" -d 'List installed packages'
+complete -c pack -n "__fish_using_command pack" -f -a "install" -d 'Install new packages/plugins'
+complete -c pack -n "__fish_using_command pack" -f -a "uninstall" -d 'Uninstall packages/plugins'
+complete -c pack -n "__fish_using_command pack" -f -a "config" -d 'Configure/edit the package specific configuration'
+complete -c pack -n "__fish_using_command pack" -f -a "move" -d 'Move a package to a different category or make it optional.'
+complete -c pack -n "__fish_using_command pack" -f -a "update" -d 'Update packages'
+complete -c pack -n "__fish_using_command pack" -f -a "outdated" -d 'List packages which have updates, without updating them'
+complete -c pack -n "__fish_using_command pack" -f -a "retry" -d 'Sync again the packages which failed in the last run'
+complete -c pack -n "__fish_using_command pack" -f -a "generate" -d 'Generate the pack package file'
+complete -c pack -n "__fish_using_command pack" -f -a "import" -d 'Import plugins from other plugin managers'
+complete -c pack -n "__fish_using_command pack" -f -a "export" -d 'Export packages to other plugin manager formats'
+complete -c pack -n "__fish_using_command pack" -f -a "completions" -d 'Generates completion scripts for your shell'
+complete -c pack -n "__fish_using_command pack" -f -a "help" -d 'Prints this message or the help of the given subcommand(s)'
+complete -c pack -n "__fish_using_command pack list" -s c -l category -d 'List packages under this category'
+complete -c pack -n "__fish_using_command pack list" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack list" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack list" -s s -l start -d 'List start packages'
+complete -c pack -n "__fish_using_command pack list" -s o -l opt -d 'List optional packages'
+complete -c pack -n "__fish_using_command pack list" -s d -l detached -d 'List detached(untracked) packages'
+complete -c pack -n "__fish_using_command pack list" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack list" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack list" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack install" -s c -l category -d 'Install package under provided category'
+complete -c pack -n "__fish_using_command pack install" -l on -d 'Commands for loading the plugins'
+complete -c pack -n "__fish_using_command pack install" -l for -d 'Load this plugins for specific types'
+complete -c pack -n "__fish_using_command pack install" -l keys -d 'Load this plugins on normal mode key mappings'
+complete -c pack -n "__fish_using_command pack install" -l event -d 'Load this plugins on an autocmd event, like `BufReadPre *.md`'
+complete -c pack -n "__fish_using_command pack install" -l build -d 'Build command for build package'
+complete -c pack -n "__fish_using_command pack install" -l requires -d 'Packages these plugins depend on, installed along with them'
+complete -c pack -n "__fish_using_command pack install" -s j -l threads -d 'Installing packages concurrently'
+complete -c pack -n "__fish_using_command pack install" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack install" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack install" -s o -l opt -d 'Install plugins as opt(ional)'
+complete -c pack -n "__fish_using_command pack install" -s l -l local -d 'Install local plugins'
+complete -c pack -n "__fish_using_command pack install" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack install" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack install" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack uninstall" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack uninstall" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack uninstall" -s a -l all -d 'remove all package related configuration as well'
+complete -c pack -n "__fish_using_command pack uninstall" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack uninstall" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack uninstall" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
 complete -c pack -n "__fish_seen_subcommand_from uninstall" -f -a "(__fish_pack_packages)"
-complete -c pack -n "__fish_seen_subcommand_from uninstall" -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_seen_subcommand_from config" -s d -l delete -d 'Delete package configuration file'
-complete -c pack -n "__fish_seen_subcommand_from config" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from config" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack config" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack config" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack config" -s d -l delete -d 'Delete package configuration file'
+complete -c pack -n "__fish_using_command pack config" -s a -l after -d 'Edit the configuration run right after the package is loaded'
+complete -c pack -n "__fish_using_command pack config" -l lua -d 'Edit the configuration written in Lua'
+complete -c pack -n "__fish_using_command pack config" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack config" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack config" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
 complete -c pack -n "__fish_seen_subcommand_from config" -f -a "(__fish_pack_packages)"
-complete -c pack -n "__fish_seen_subcommand_from move" -s o -l opt -d 'Make package optional'
-complete -c pack -n "__fish_seen_subcommand_from move" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from move" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack move" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack move" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack move" -s o -l opt -d 'Make package optional'
+complete -c pack -n "__fish_using_command pack move" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack move" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack move" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
 complete -c pack -n "__fish_seen_subcommand_from move" -f -a "(__fish_pack_packages)"
-complete -c pack -n "__fish_seen_subcommand_from update" -s s -l skip -d 'Skip packages'
-complete -c pack -n "__fish_seen_subcommand_from update" -s p -l packfile -d 'Regenerate the \'_pack\' file (combine all package configurations)'
-complete -c pack -n "__fish_seen_subcommand_from update" -s j -l threads -d 'Updating packages concurrently'
-complete -c pack -n "__fish_seen_subcommand_from update" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from update" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack update" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack update" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack update" -s s -l skip -d 'Skip packages'
+complete -c pack -n "__fish_using_command pack update" -s p -l packfile -d 'Regenerate the \'_pack\' file (combine all package configurations)'
+complete -c pack -n "__fish_using_command pack update" -s j -l threads -d 'Updating packages concurrently'
+complete -c pack -n "__fish_using_command pack update" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack update" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack update" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
 complete -c pack -n "__fish_seen_subcommand_from update" -f -a "(__fish_pack_packages)"
-complete -c pack -n "__fish_seen_subcommand_from generate" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from generate" -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_seen_subcommand_from completions" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from completions" -s V -l version -d 'Prints version information'
-complete -c pack -n "__fish_seen_subcommand_from help" -s h -l help -d 'Prints help information'
-complete -c pack -n "__fish_seen_subcommand_from help" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack outdated" -s j -l threads -d 'Checking packages concurrently'
+complete -c pack -n "__fish_using_command pack outdated" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack outdated" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack outdated" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack outdated" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack outdated" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_seen_subcommand_from outdated" -f -a "(__fish_pack_packages)"
+complete -c pack -n "__fish_using_command pack retry" -s j -l threads -d 'Syncing packages concurrently'
+complete -c pack -n "__fish_using_command pack retry" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack retry" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack retry" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack retry" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack retry" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack generate" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack generate" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack generate" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack generate" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack generate" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack import" -s f -l from -d 'Plugin manager the plugins are imported from' -r -f -a "vim-plug vundle dein packer lazy"
+complete -c pack -n "__fish_using_command pack import" -s c -l category -d 'Import packages under provided category'
+complete -c pack -n "__fish_using_command pack import" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack import" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack import" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack import" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack import" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack export" -s f -l format -d 'Format the packages are exported in' -r -f -a "vim-plug lua json"
+complete -c pack -n "__fish_using_command pack export" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack export" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack export" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack export" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack export" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack completions" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack completions" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack completions" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack completions" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack completions" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
+complete -c pack -n "__fish_using_command pack help" -s P -l profile -d 'Packfile profile to operate on [env: PACK_PROFILE]'
+complete -c pack -n "__fish_using_command pack help" -l output -d 'Print progress as text or as newline-delimited JSON events' -r -f -a "text json"
+complete -c pack -n "__fish_using_command pack help" -s h -l help -d 'Prints help information'
+complete -c pack -n "__fish_using_command pack help" -s V -l version -d 'Prints version information'
+complete -c pack -n "__fish_using_command pack help" -l no-tty -d 'Print one line per package state change instead of redrawing the terminal'
@@ -5,6 +5,51 @@ pub fn build_cli() -> App<'static, 'static> {
         .about("Package manager for vim")
         .author(clap::crate_authors!())
         .version(clap::crate_version!())
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .short("P")
+                .global(true)
+                .help("Packfile profile to operate on [env: PACK_PROFILE]")
+                .value_name("PROFILE"),
+        )
+        .arg(
+            Arg::with_name("root")
+                .long("root")
+                .help("Vim config directory to operate on [env: VIM_CONFIG_PATH]")
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .short("t")
+                .possible_values(&["vim", "neovim"])
+                .multiple(true)
+                .number_of_values(1)
+                .use_delimiter(true)
+                .help("Editors to manage packages for, detected by default [env: PACK_TARGET]")
+                .value_name("EDITOR"),
+        )
+        .arg(
+            Arg::with_name("no-tty")
+                .long("no-tty")
+                .global(true)
+                .help("Print one line per package state change instead of redrawing the terminal"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .global(true)
+                .possible_values(&["text", "json"])
+                .help("Print progress as text or as newline-delimited JSON events")
+                .value_name("FORMAT"),
+        )
+        .arg(
+            Arg::with_name("packfile")
+                .long("packfile")
+                .help("Packfile to use instead of the one of the profile [env: PACK_FILE]")
+                .value_name("FILE"),
+        )
         .subcommand(
             SubCommand::with_name("list")
                 .about("List installed packages")
@@ -62,7 +107,7 @@ pub fn build_cli() -> App<'static, 'static> {
                 .arg(
                     Arg::with_name("on")
                         .long("on")
-                        .help("Command for loading the plugins")
+                        .help("Commands for loading the plugins")
                         .value_name("LOAD_CMD"),
                 )
                 .arg(
@@ -71,12 +116,32 @@ pub fn build_cli() -> App<'static, 'static> {
                         .help("Load this plugins for specific types")
                         .value_name("TYPES"),
                 )
+                .arg(
+                    Arg::with_name("keys")
+                        .long("keys")
+                        .help("Load this plugins on normal mode key mappings")
+                        .value_name("MAPPINGS"),
+                )
+                .arg(
+                    Arg::with_name("event")
+                        .long("event")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Load this plugins on an autocmd event, like `BufReadPre *.md`")
+                        .value_name("EVENT"),
+                )
                 .arg(
                     Arg::with_name("build")
                         .long("build")
                         .help("Build command for build package")
                         .value_name("BUILD_CMD"),
                 )
+                .arg(
+                    Arg::with_name("requires")
+                        .long("requires")
+                        .help("Packages these plugins depend on, installed along with them")
+                        .value_name("PACKAGES"),
+                )
                 .arg(
                     Arg::with_name("threads")
                         .short("j")
@@ -106,6 +171,17 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("delete")
                         .help("Delete package configuration file"),
                 )
+                .arg(
+                    Arg::with_name("after")
+                        .short("a")
+                        .long("after")
+                        .help("Edit the configuration run right after the package is loaded"),
+                )
+                .arg(
+                    Arg::with_name("lua")
+                        .long("lua")
+                        .help("Edit the configuration written in Lua"),
+                )
                 .arg(Arg::with_name("package").required(true)),
         )
         .subcommand(
@@ -157,11 +233,77 @@ pub fn build_cli() -> App<'static, 'static> {
                         .multiple(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("outdated")
+                .about("List packages which have updates, without updating them")
+                .arg(
+                    Arg::with_name("threads")
+                        .short("j")
+                        .long("threads")
+                        .help("Checking packages concurrently")
+                        .value_name("THREADS"),
+                )
+                .arg(
+                    Arg::with_name("package")
+                        .help("Packages to check, default all")
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("retry")
+                .about("Sync again the packages which failed in the last run")
+                .arg(
+                    Arg::with_name("threads")
+                        .short("j")
+                        .long("threads")
+                        .help("Syncing packages concurrently")
+                        .value_name("THREADS"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("generate")
                 .about("Generate the pack package file")
                 .help("Generate _pack.vim file which combines all package configurations"),
         )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import plugins from other plugin managers")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .short("f")
+                        .help("Plugin manager the plugins are imported from")
+                        .possible_values(&["vim-plug", "vundle", "dein", "packer", "lazy"])
+                        .required(true)
+                        .value_name("MANAGER"),
+                )
+                .arg(
+                    Arg::with_name("category")
+                        .long("category")
+                        .short("c")
+                        .help("Import packages under provided category")
+                        .default_value("default")
+                        .value_name("CATEGORY"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("vimrc, Lua spec file or directory declaring the plugins")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Export packages to other plugin manager formats")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .short("f")
+                        .help("Format the packages are exported in")
+                        .possible_values(&["vim-plug", "lua", "json"])
+                        .default_value("vim-plug")
+                        .value_name("FORMAT"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("completions")
                 .about("Generates completion scripts for your shell")
@@ -1,6 +1,8 @@
-use crate::package;
-use crate::utils;
-use crate::{Error, Result};
+use pack::context::Context;
+use pack::echo::Output;
+use pack::package;
+use pack::utils;
+use pack::{Error, Result};
 
 use clap::{value_t, ArgMatches};
 use std::fs;
@@ -10,6 +12,9 @@ use std::io::ErrorKind;
 struct ConfigArgs {
     plugin: String,
     delete: bool,
+    after: bool,
+    lua: bool,
+    output: Output,
 }
 
 impl ConfigArgs {
@@ -17,24 +22,31 @@ impl ConfigArgs {
         ConfigArgs {
             plugin: value_t!(m, "package", String).unwrap_or_default(),
             delete: m.is_present("delete"),
+            after: m.is_present("after"),
+            lua: m.is_present("lua"),
+            output: super::output(m),
         }
     }
 }
 
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
     let args = ConfigArgs::from_matches(matches);
 
-    if let Err(e) = config_plugin(&args.plugin, args.delete) {
+    if let Err(e) = config_plugin(ctxs, &args) {
         die!("{}", e);
     }
 }
 
-fn config_plugin(name: &str, delete: bool) -> Result<()> {
-    let packs = package::fetch()?;
+fn config_plugin(ctxs: &[Context], args: &ConfigArgs) -> Result<()> {
+    let name = args.plugin.as_str();
+    let delete = args.delete;
+    let ctx = &ctxs[0];
+    let packs = package::fetch(ctx)?;
     let temp_pack = package::Package::new(name, "temp", true);
     let pack = packs.iter().find(|x| name == x.name).unwrap_or(&temp_pack);
 
-    let path = pack.config_path();
+    let [vim, lua] = pack.config_files(ctx, args.after);
+    let path = if args.lua { lua } else { vim };
 
     let modified = match fs::metadata(&path) {
         Err(e) => {
@@ -53,6 +65,7 @@ fn config_plugin(name: &str, delete: bool) -> Result<()> {
     }
 
     utils::open_editor(&path)?;
+    let _lock = super::lock(ctx, args.output);
 
     let meta = match fs::metadata(&path) {
         Err(e) => {
@@ -68,10 +81,17 @@ fn config_plugin(name: &str, delete: bool) -> Result<()> {
     if meta.len() == 0 {
         fs::remove_file(&path)?;
         if modified.is_some() {
-            package::update_pack_plugin(&packs)?;
+            update_pack_plugins(ctxs, &packs)?;
         }
     } else if modified.is_none() || meta.modified()? > modified.unwrap() {
-        package::update_pack_plugin(&packs)?;
+        update_pack_plugins(ctxs, &packs)?;
+    }
+    Ok(())
+}
+
+fn update_pack_plugins(ctxs: &[Context], packs: &[package::Package]) -> Result<()> {
+    for ctx in ctxs {
+        package::update_pack_plugin(ctx, packs)?;
     }
     Ok(())
 }
@@ -0,0 +1,447 @@
+use pack::condition::Condition;
+use pack::context::Context;
+use pack::keymap::KeyMap;
+use pack::package::{self, Package};
+use pack::utils::{json_string, lua_string, vim_string};
+use pack::Result;
+
+use clap::{value_t, ArgMatches};
+
+#[derive(Debug)]
+struct ExportArgs {
+    format: String,
+}
+
+impl ExportArgs {
+    fn from_matches(m: &ArgMatches) -> ExportArgs {
+        ExportArgs {
+            format: value_t!(m, "format", String).unwrap_or_default(),
+        }
+    }
+}
+
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let ctx = &ctxs[0];
+    let args = ExportArgs::from_matches(matches);
+
+    let render = match args.format.as_str() {
+        "vim-plug" => render_vim_plug,
+        "lua" => render_lua,
+        "json" => render_json,
+        f => die!("Unknown export format: {}", f),
+    };
+
+    if let Err(e) = export_packages(ctx, render) {
+        die!("Err: {}", e);
+    }
+}
+
+/// Renders packages, adding a warning for each setting the format can't express.
+type Render = fn(&[Package], &mut Vec<String>) -> String;
+
+fn export_packages(ctx: &Context, render: Render) -> Result<()> {
+    let mut packs = package::fetch(ctx)?;
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut warnings = Vec::new();
+    print!("{}", render(&packs, &mut warnings));
+    for w in warnings {
+        eprintln!("Warning: {}", w);
+    }
+    Ok(())
+}
+
+/// Warn about the conditions checked when syncing, which other managers don't have.
+fn warn_static_condition(p: &Package, warnings: &mut Vec<String>) {
+    let mut keys = Vec::new();
+    for (key, items) in [
+        ("host", &p.when.host),
+        ("os", &p.when.os),
+        ("env", &p.when.env),
+    ] {
+        if !items.is_empty() {
+            keys.push(key);
+        }
+    }
+    if !keys.is_empty() {
+        warnings.push(format!(
+            "{}: `when` {} not exported",
+            p.name,
+            keys.join(", ")
+        ));
+    }
+}
+
+fn render_list<F>(items: &[String], open: &str, close: &str, quote: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let items = items.iter().map(|e| quote(e)).collect::<Vec<String>>();
+    format!("{}{}{}", open, items.join(", "), close)
+}
+
+fn render_vim_plug(packs: &[Package], warnings: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    // vim-plug's way to enable a plugin conditionally, from its wiki.
+    if packs.iter().any(|p| p.when.is_runtime()) {
+        out.push_str(
+            "function! Cond(cond, ...)
+  let opts = get(a:000, 0, {})
+  return a:cond ? opts : extend(opts, { 'on': [], 'for': [] })
+endfunction
+
+",
+        );
+    }
+    out.push_str("call plug#begin()\n");
+    // Events and functions have no option, they load the plugin through autocmds.
+    let mut autocmds = Vec::new();
+    for p in packs {
+        let mut opts = Vec::new();
+        // vim-plug loads on commands and on `<Plug>` mappings in every mode.
+        let mut on = p.load_commands.clone();
+        for k in &p.keys {
+            if !k.lhs.starts_with("<Plug>") {
+                warnings.push(format!(
+                    "{}: key {} not exported, vim-plug only loads on <Plug> mappings",
+                    p.name, k.lhs
+                ));
+            } else if !on.contains(&k.lhs) {
+                on.push(k.lhs.clone());
+            }
+        }
+        if !p.modules.is_empty() {
+            warnings.push(format!(
+                "{}: on_module not exported, vim-plug can't load on Lua modules",
+                p.name
+            ));
+        }
+        warn_static_condition(p, warnings);
+        if on.len() == 1 {
+            opts.push(format!("'on': {}", vim_string(&on[0])));
+        } else if !on.is_empty() {
+            opts.push(format!("'on': {}", render_list(&on, "[", "]", vim_string)));
+        } else if p.opt && p.for_types.is_empty() {
+            // Optional packages without triggers are only loaded manually.
+            opts.push(String::from("'on': []"));
+        }
+        if !p.for_types.is_empty() {
+            opts.push(format!(
+                "'for': {}",
+                render_list(&p.for_types, "[", "]", vim_string)
+            ));
+        }
+        if let Some(ref c) = p.build_command {
+            opts.push(format!("'do': {}", vim_string(c)));
+        }
+
+        let opts = if opts.is_empty() {
+            None
+        } else {
+            Some(format!("{{ {} }}", opts.join(", ")))
+        };
+        let cond = p.when.vim_expr();
+        out.push_str(&format!("Plug {}", vim_string(&p.name)));
+        match (&cond, opts) {
+            (Some(c), Some(o)) => out.push_str(&format!(", Cond({}, {})", c, o)),
+            (Some(c), None) => out.push_str(&format!(", Cond({})", c)),
+            (None, Some(o)) => out.push_str(&format!(", {}", o)),
+            (None, None) => {}
+        }
+        out.push('\n');
+
+        let load = format!("call plug#load({})", vim_string(p.dir_name()));
+        let triggers = p
+            .trigger_events()
+            .into_iter()
+            .map(|(e, pattern)| format!("autocmd {} {} ++once {}", e, pattern, load))
+            .collect::<Vec<String>>();
+        if !triggers.is_empty() {
+            autocmds.push(match cond {
+                Some(c) => format!("  if {}\n    {}\n  endif", c, triggers.join("\n    ")),
+                None => format!("  {}", triggers.join("\n  ")),
+            });
+        }
+    }
+    out.push_str("call plug#end()\n");
+    if !autocmds.is_empty() {
+        out.push_str("\naugroup pack_export\n  autocmd!\n");
+        for a in autocmds {
+            out.push_str(&a);
+            out.push('\n');
+        }
+        out.push_str("augroup END\n");
+    }
+    out
+}
+
+fn render_lua(packs: &[Package], warnings: &mut Vec<String>) -> String {
+    let mut out = String::from("return {\n");
+    for p in packs {
+        warn_static_condition(p, warnings);
+        let mut opts = vec![lua_string(&p.name)];
+        if p.local {
+            opts.push(format!("dir = {}", lua_string(&p.name)));
+        }
+        if p.opt {
+            opts.push(String::from("lazy = true"));
+        }
+        if p.load_commands.len() == 1 {
+            opts.push(format!("cmd = {}", lua_string(&p.load_commands[0])));
+        } else if !p.load_commands.is_empty() {
+            opts.push(format!(
+                "cmd = {}",
+                render_list(&p.load_commands, "{ ", " }", lua_string)
+            ));
+        }
+        if !p.for_types.is_empty() {
+            opts.push(format!(
+                "ft = {}",
+                render_list(&p.for_types, "{ ", " }", lua_string)
+            ));
+        }
+        if !p.keys.is_empty() {
+            let keys = p.keys.iter().map(render_lua_key).collect::<Vec<String>>();
+            opts.push(format!("keys = {{ {} }}", keys.join(", ")));
+        }
+        // Lazy Lua packages are loaded on `require` of their modules anyway.
+        let events = p
+            .trigger_events()
+            .into_iter()
+            .map(|(e, pattern)| {
+                if pattern == "*" {
+                    e.to_string()
+                } else {
+                    format!("{} {}", e, pattern)
+                }
+            })
+            .collect::<Vec<String>>();
+        if !events.is_empty() {
+            opts.push(format!(
+                "event = {}",
+                render_list(&events, "{ ", " }", lua_string)
+            ));
+        }
+        if let Some(ref c) = p.build_command {
+            opts.push(format!("build = {}", lua_string(c)));
+        }
+        if let Some(expr) = p.when.lua_expr() {
+            opts.push(format!("cond = function() return {} end", expr));
+        }
+        if !p.requires.is_empty() {
+            opts.push(format!(
+                "dependencies = {}",
+                render_list(&p.requires, "{ ", " }", lua_string)
+            ));
+        }
+        out.push_str(&format!("  {{ {} }},\n", opts.join(", ")));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_lua_key(key: &KeyMap) -> String {
+    if key.modes == ["n"] {
+        return lua_string(&key.lhs);
+    }
+    format!(
+        "{{ {}, mode = {} }}",
+        lua_string(&key.lhs),
+        render_list(&key.modes, "{ ", " }", lua_string)
+    )
+}
+
+fn render_json(packs: &[Package], _: &mut Vec<String>) -> String {
+    let items = packs
+        .iter()
+        .map(|p| format!("  {}", json_package(p)))
+        .collect::<Vec<String>>();
+    if items.is_empty() {
+        String::from("[]\n")
+    } else {
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+}
+
+/// A package as a JSON object with the fields of its packfile entry.
+pub(super) fn json_package(p: &Package) -> String {
+    let mut fields = vec![
+        format!("\"name\": {}", json_string(&p.name)),
+        format!("\"category\": {}", json_string(&p.category)),
+        format!("\"opt\": {}", p.opt),
+        format!("\"local\": {}", p.local),
+    ];
+    // Like in the packfile, a single command is a string.
+    if p.load_commands.len() == 1 {
+        fields.push(format!("\"on\": {}", json_string(&p.load_commands[0])));
+    } else if !p.load_commands.is_empty() {
+        fields.push(format!(
+            "\"on\": {}",
+            render_list(&p.load_commands, "[", "]", json_string)
+        ));
+    }
+    fields.push(format!(
+        "\"for\": {}",
+        render_list(&p.for_types, "[", "]", json_string)
+    ));
+    if !p.keys.is_empty() {
+        let keys = p
+            .keys
+            .iter()
+            .map(|k| {
+                format!(
+                    "{{\"lhs\": {}, \"mode\": {}}}",
+                    json_string(&k.lhs),
+                    render_list(&k.modes, "[", "]", json_string)
+                )
+            })
+            .collect::<Vec<String>>();
+        fields.push(format!("\"keys\": [{}]", keys.join(", ")));
+    }
+    if !p.events.is_empty() {
+        fields.push(format!(
+            "\"event\": {}",
+            render_list(&p.events, "[", "]", json_string)
+        ));
+    }
+    if !p.funcs.is_empty() {
+        fields.push(format!(
+            "\"on_func\": {}",
+            render_list(&p.funcs, "[", "]", json_string)
+        ));
+    }
+    if !p.modules.is_empty() {
+        fields.push(format!(
+            "\"on_module\": {}",
+            render_list(&p.modules, "[", "]", json_string)
+        ));
+    }
+    if let Some(ref c) = p.build_command {
+        fields.push(format!("\"build\": {}", json_string(c)));
+    }
+    fields.push(format!(
+        "\"requires\": {}",
+        render_list(&p.requires, "[", "]", json_string)
+    ));
+    if !p.when.is_empty() {
+        fields.push(format!("\"when\": {}", render_json_condition(&p.when)));
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn render_json_condition(cond: &Condition) -> String {
+    let mut fields = Vec::new();
+    if let Some(ref e) = cond.editor {
+        fields.push(format!("\"editor\": {}", json_string(e)));
+    }
+    for (key, items) in [
+        ("host", &cond.host),
+        ("os", &cond.os),
+        ("env", &cond.env),
+        ("has", &cond.has),
+    ] {
+        if !items.is_empty() {
+            fields.push(format!(
+                "\"{}\": {}",
+                key,
+                render_list(items, "[", "]", json_string)
+            ));
+        }
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packs() -> Vec<Package> {
+        let mut lazy = Package::new("scrooloose/nerdtree", "default", true);
+        lazy.set_load_commands(vec!["NERDTreeToggle".to_string()]);
+        lazy.set_keys(vec![
+            KeyMap {
+                lhs: String::from("<Plug>NERDTree"),
+                modes: vec![String::from("n"), String::from("x")],
+            },
+            KeyMap::new("<Leader>n"),
+        ]);
+        let mut typed = Package::new("fatih/vim-go", "lang", true);
+        typed.set_types(vec!["go".to_string()]);
+        typed.set_events(vec!["BufReadPre *.mod".to_string()]);
+        typed.set_build_command("make 'all'");
+        typed.set_requires(vec!["a/lib".to_string()]);
+        typed.when.os.push("linux".to_string());
+        let mut triggered = Package::new("junegunn/fzf", "default", true);
+        triggered.set_events(vec!["VimEnter".to_string()]);
+        triggered.set_funcs(vec!["fzf#".to_string()]);
+        triggered.when.editor = Some("neovim".to_string());
+        vec![
+            lazy,
+            typed,
+            triggered,
+            Package::new("tpope/vim-sensible", "default", false),
+        ]
+    }
+
+    #[test]
+    fn export_vim_plug() {
+        let exp = "function! Cond(cond, ...)
+  let opts = get(a:000, 0, {})
+  return a:cond ? opts : extend(opts, { 'on': [], 'for': [] })
+endfunction
+
+call plug#begin()
+Plug 'scrooloose/nerdtree', { 'on': ['NERDTreeToggle', '<Plug>NERDTree'] }
+Plug 'fatih/vim-go', { 'for': ['go'], 'do': 'make ''all''' }
+Plug 'junegunn/fzf', Cond(has('nvim'), { 'on': [] })
+Plug 'tpope/vim-sensible'
+call plug#end()
+
+augroup pack_export
+  autocmd!
+  autocmd BufReadPre *.mod ++once call plug#load('vim-go')
+  if has('nvim')
+    autocmd VimEnter * ++once call plug#load('fzf')
+    autocmd FuncUndefined fzf#* ++once call plug#load('fzf')
+  endif
+augroup END
+";
+        let mut warnings = Vec::new();
+        assert_eq!(render_vim_plug(&packs(), &mut warnings), exp);
+        assert_eq!(
+            warnings,
+            vec![
+                "scrooloose/nerdtree: key <Leader>n not exported, \
+                 vim-plug only loads on <Plug> mappings",
+                "fatih/vim-go: `when` os not exported",
+            ]
+        );
+    }
+
+    #[test]
+    fn export_lua() {
+        let exp = "return {
+  { 'scrooloose/nerdtree', lazy = true, cmd = 'NERDTreeToggle', \
+keys = { { '<Plug>NERDTree', mode = { 'n', 'x' } }, '<Leader>n' } },
+  { 'fatih/vim-go', lazy = true, ft = { 'go' }, event = { 'BufReadPre *.mod' }, build = 'make \\'all\\'', dependencies = { 'a/lib' } },
+  { 'junegunn/fzf', lazy = true, event = { 'VimEnter', 'FuncUndefined fzf#*' }, \
+cond = function() return vim.fn.has('nvim') == 1 end },
+  { 'tpope/vim-sensible' },
+}
+";
+        let mut warnings = Vec::new();
+        assert_eq!(render_lua(&packs(), &mut warnings), exp);
+        assert_eq!(warnings, vec!["fatih/vim-go: `when` os not exported"]);
+    }
+
+    #[test]
+    fn export_json() {
+        let out = render_json(&packs()[1..2], &mut Vec::new());
+        let exp = "[
+  {\"name\": \"fatih/vim-go\", \"category\": \"lang\", \"opt\": true, \"local\": false, \
+\"for\": [\"go\"], \"event\": [\"BufReadPre *.mod\"], \"build\": \"make 'all'\", \"requires\": [\"a/lib\"], \"when\": {\"os\": [\"linux\"]}}
+]
+";
+        assert_eq!(out, exp);
+        assert_eq!(render_json(&[], &mut Vec::new()), "[]\n");
+    }
+}
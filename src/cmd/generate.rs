@@ -1,16 +1,10 @@
-use crate::package;
-use crate::Result;
 use clap::ArgMatches;
-
-pub fn exec(_matches: &ArgMatches) {
-    let _ = update_packfile();
-}
-
-fn update_packfile() -> Result<()> {
-    let mut packs = package::fetch()?;
-
-    packs.sort_by(|a, b| a.name.cmp(&b.name));
-    package::update_pack_plugin(&packs)?;
-
-    Ok(())
+use pack::context::Context;
+use pack::package;
+
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let _lock = super::lock(&ctxs[0], super::output(matches));
+    for ctx in ctxs {
+        let _ = package::generate(ctx);
+    }
 }
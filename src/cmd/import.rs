@@ -0,0 +1,826 @@
+use pack::context::Context;
+use pack::keymap::{self, KeyMap};
+use pack::package::{self, Package};
+use pack::Result;
+
+use clap::{value_t, ArgMatches};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+struct ImportArgs {
+    from: String,
+    file: String,
+    category: String,
+}
+
+impl ImportArgs {
+    fn from_matches(m: &ArgMatches) -> ImportArgs {
+        ImportArgs {
+            from: value_t!(m, "from", String).unwrap_or_default(),
+            file: value_t!(m, "file", String).unwrap_or_default(),
+            category: value_t!(m, "category", String).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    VimPlug,
+    Vundle,
+    Dein,
+    Lua,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "vim-plug" => Some(Format::VimPlug),
+            "vundle" => Some(Format::Vundle),
+            "dein" => Some(Format::Dein),
+            "packer" | "lazy" => Some(Format::Lua),
+            _ => None,
+        }
+    }
+}
+
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let ctx = &ctxs[0];
+    let args = ImportArgs::from_matches(matches);
+    let _lock = super::lock(ctx, super::output(matches));
+
+    let format = match Format::from_name(&args.from) {
+        Some(f) => f,
+        None => die!("Unknown plugin manager: {}", args.from),
+    };
+
+    if let Err(e) = import_plugins(ctx, format, &args.file, &args.category) {
+        die!("Err: {}", e);
+    }
+}
+
+fn import_plugins(ctx: &Context, format: Format, file: &str, category: &str) -> Result<()> {
+    let mut packfile = package::load(ctx)?;
+    let packs = &mut packfile.packs;
+
+    let mut imported = 0;
+    for spec in parse(format, &read_source(file)?) {
+        let pack = match spec.into_package(category) {
+            Some(p) => p,
+            None => continue,
+        };
+        if packs.iter().any(|x| x.name == pack.name) {
+            println!("Skip {}: already in packfile", pack.name);
+            continue;
+        }
+        println!("Import {}", pack);
+        packs.push(pack);
+        imported += 1;
+    }
+
+    if imported == 0 {
+        println!("Nothing to import.");
+        return Ok(());
+    }
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packfile.save(ctx)?;
+    println!(
+        "Imported {} packages. Run `pack install` to install them.",
+        imported
+    );
+    Ok(())
+}
+
+/// Read the plugin declarations from a file or from every file directly under a directory.
+fn read_source(file: &str) -> Result<String> {
+    let path = Path::new(file);
+    if !path.is_dir() {
+        return Ok(fs::read_to_string(path)?);
+    }
+
+    let mut files = path
+        .read_dir()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+
+    let mut data = String::new();
+    for f in files {
+        data.push_str(&fs::read_to_string(f)?);
+        data.push('\n');
+    }
+    Ok(data)
+}
+
+/// A plugin declaration found in another plugin manager's configuration.
+#[derive(Debug, Default, PartialEq)]
+struct Spec {
+    name: String,
+    opt: bool,
+    on: Vec<String>,
+    types: Vec<String>,
+    keys: Vec<KeyMap>,
+    events: Vec<String>,
+    funcs: Vec<String>,
+    modules: Vec<String>,
+    build: Option<String>,
+    /// Vim command build hook, which pack can not run
+    vim_build: Option<String>,
+    requires: Vec<String>,
+}
+
+impl Spec {
+    fn new(name: &str) -> Spec {
+        Spec {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn into_package(self, category: &str) -> Option<Package> {
+        let (name, local) = match normalize_name(&self.name) {
+            Some(n) => n,
+            None => {
+                println!(
+                    "Skip {}: only github and local plugins are supported",
+                    self.name
+                );
+                return None;
+            }
+        };
+
+        let opt = self.opt
+            || !self.on.is_empty()
+            || !self.types.is_empty()
+            || !self.keys.is_empty()
+            || !self.events.is_empty()
+            || !self.funcs.is_empty()
+            || !self.modules.is_empty();
+        let mut pack = Package::new(&name, category, opt);
+        pack.local = local;
+        pack.set_load_commands(self.on);
+        pack.set_types(self.types);
+        pack.set_keys(self.keys);
+        pack.set_events(self.events);
+        pack.set_funcs(self.funcs);
+        pack.set_modules(self.modules);
+        if let Some(ref c) = self.build {
+            pack.set_build_command(c);
+        }
+        if let Some(ref c) = self.vim_build {
+            eprintln!(
+                "Warning: {}: build hook `{}` is a vim command and is not imported",
+                name, c
+            );
+        }
+        pack.set_requires(
+            self.requires
+                .iter()
+                .filter_map(|r| normalize_name(r))
+                .map(|(r, _)| r)
+                .collect(),
+        );
+        Some(pack)
+    }
+
+    /// Fill the spec from the options of a declaration. Unknown options are ignored.
+    fn apply(&mut self, key: &str, value: &Value) {
+        match key {
+            "on" | "on_cmd" | "cmd" => {
+                // vim-plug loads on `<Plug>` mappings in every mode.
+                for c in value.strings() {
+                    if c.starts_with('<') {
+                        self.keys.push(key_map(&c, &["n", "v", "o", "i"]));
+                    } else {
+                        self.on.push(c);
+                    }
+                }
+            }
+            "on_map" => self.keys.extend(value.key_maps(&["n", "x", "o"])),
+            "keys" => self.keys.extend(value.key_maps(&["n"])),
+            "event" | "on_event" => self.events.extend(value.strings()),
+            "fn" | "on_func" => self.funcs.extend(value.strings()),
+            "module" | "on_lua" => self.modules.extend(value.strings()),
+            "for" | "on_ft" | "ft" => self.types.extend(value.strings()),
+            "do" | "build" | "run" => {
+                // Vim commands can not be run by the build shell.
+                if let Value::Str(ref s) = *value {
+                    if s.starts_with(':') {
+                        self.vim_build = Some(s.clone());
+                    } else {
+                        self.build = Some(s.clone());
+                    }
+                }
+            }
+            "lazy" | "opt" => self.opt = value.is_true(),
+            "depends" | "requires" | "dependencies" => self.requires.extend(value.specs()),
+            _ => {}
+        }
+    }
+}
+
+fn key_map(lhs: &str, modes: &[&str]) -> KeyMap {
+    KeyMap {
+        lhs: lhs.to_string(),
+        modes: modes.iter().map(|m| m.to_string()).collect(),
+    }
+}
+
+/// Convert a plugin reference to a packfile name. Returns the name and whether
+/// it is a local plugin.
+fn normalize_name(name: &str) -> Option<(String, bool)> {
+    if name.starts_with('/') || name.starts_with('.') {
+        return Some((name.to_string(), true));
+    }
+    if let Some(rest) = name.strip_prefix("~/") {
+        let home = dirs::home_dir()?;
+        return Some((home.join(rest).to_string_lossy().into_owned(), true));
+    }
+
+    let repo = [
+        "https://github.com/",
+        "http://github.com/",
+        "git@github.com:",
+    ]
+    .iter()
+    .find_map(|p| name.strip_prefix(p))
+    .unwrap_or(name);
+    let repo = repo.trim_end_matches('/');
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    if is_repo(repo) {
+        Some((repo.to_string(), false))
+    } else {
+        None
+    }
+}
+
+fn is_repo(s: &str) -> bool {
+    let mut parts = s.split('/');
+    let valid = |p: Option<&str>| {
+        p.is_some_and(|p| {
+            !p.is_empty()
+                && p.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c))
+        })
+    };
+    valid(parts.next()) && valid(parts.next()) && parts.next().is_none()
+}
+
+fn parse(format: Format, data: &str) -> Vec<Spec> {
+    match format {
+        Format::Lua => parse_lua(data),
+        _ => parse_vim(format, data),
+    }
+}
+
+fn parse_vim(format: Format, data: &str) -> Vec<Spec> {
+    let mut specs = Vec::new();
+    for line in vim_lines(data) {
+        let tokens = tokenize(&line, false);
+        let args = match (format, tokens.as_slice()) {
+            (Format::VimPlug, [Token::Ident(c), rest @ ..]) if c == "Plug" => rest,
+            (Format::Vundle, [Token::Ident(c), rest @ ..]) if c == "Plugin" || c == "Bundle" => {
+                rest
+            }
+            (Format::Dein, [Token::Ident(c), Token::Ident(f), Token::Punct('('), rest @ ..])
+                if c == "call" && f == "dein#add" =>
+            {
+                rest
+            }
+            _ => continue,
+        };
+
+        let mut parser = Parser::new(args);
+        let name = match parser.value() {
+            Value::Str(s) => s,
+            _ => continue,
+        };
+        let mut spec = Spec::new(&name);
+        if parser.eat(',') {
+            if let Value::Table(t) = parser.value() {
+                for (k, v) in &t.fields {
+                    spec.apply(k, v);
+                }
+            }
+        }
+        specs.push(spec);
+    }
+    specs
+}
+
+/// Join continuation lines and drop comments.
+fn vim_lines(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let line = line.trim_start();
+        if line.starts_with('"') {
+            continue;
+        }
+        match (line.strip_prefix('\\'), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_lua(data: &str) -> Vec<Spec> {
+    let tokens = tokenize(data, true);
+    let mut parser = Parser::new(&tokens);
+    let mut specs = Vec::new();
+    while let Some(tok) = parser.peek() {
+        match *tok {
+            Token::Punct('{') => collect_lua(&parser.value(), &mut specs),
+            Token::Ident(ref i) if i == "use" => {
+                parser.next();
+                parser.eat('(');
+                // Tables passed to `use` are handled as any other table.
+                if let Some(Token::Str(s)) = parser.peek() {
+                    parser.next();
+                    if is_lua_repo(s) {
+                        specs.push(Spec::new(s));
+                    }
+                }
+            }
+            _ => {
+                parser.next();
+            }
+        }
+    }
+    specs
+}
+
+fn is_lua_repo(s: &str) -> bool {
+    normalize_name(s).is_some_and(|(_, local)| !local)
+}
+
+/// Walk a Lua table and collect every plugin spec in it, including nested
+/// dependencies.
+fn collect_lua(value: &Value, specs: &mut Vec<Spec>) {
+    let table = match *value {
+        Value::Table(ref t) => t,
+        _ => return,
+    };
+
+    for (i, item) in table.items.iter().enumerate() {
+        match *item {
+            Value::Str(ref s) if is_lua_repo(s) => {
+                let mut spec = Spec::new(s);
+                if i == 0 {
+                    for (k, v) in &table.fields {
+                        spec.apply(k, v);
+                    }
+                }
+                specs.push(spec);
+            }
+            Value::Table(_) => collect_lua(item, specs),
+            _ => {}
+        }
+    }
+
+    for (k, v) in &table.fields {
+        match *v {
+            Value::Str(ref s) if (k == "requires" || k == "dependencies") && is_lua_repo(s) => {
+                specs.push(Spec::new(s))
+            }
+            Value::Table(_) => collect_lua(v, specs),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Ident(String),
+    Punct(char),
+}
+
+fn tokenize(data: &str, lua: bool) -> Vec<Token> {
+    let chars: Vec<char> = data.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if lua && c == '-' && chars.get(i + 1) == Some(&'-') {
+            i += 2;
+            if chars.get(i) == Some(&'[') && chars.get(i + 1) == Some(&'[') {
+                i = lua_long_string(&chars, i).1;
+            } else {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+        } else if lua && c == '[' && chars.get(i + 1) == Some(&'[') {
+            let (s, end) = lua_long_string(&chars, i);
+            tokens.push(Token::Str(s));
+            i = end;
+        } else if c == '\'' || c == '"' {
+            let mut s = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && (c == '"' || lua) && i + 1 < chars.len() {
+                    i += 1;
+                } else if chars[i] == '\'' && !lua && chars.get(i + 1) == Some(&'\'') {
+                    // Vim literal strings escape a single quote by doubling it.
+                    i += 1;
+                }
+                s.push(chars[i]);
+                i += 1;
+            }
+            tokens.push(Token::Str(s));
+            i += 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || "_#.:".contains(chars[i])) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// Read a Lua long bracket string starting at `start`. Returns the content and the
+/// position after the closing bracket.
+fn lua_long_string(chars: &[char], start: usize) -> (String, usize) {
+    let mut i = start + 2;
+    let mut s = String::new();
+    while i < chars.len() {
+        if chars[i] == ']' && chars.get(i + 1) == Some(&']') {
+            return (s, i + 2);
+        }
+        s.push(chars[i]);
+        i += 1;
+    }
+    (s, i)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Table {
+    items: Vec<Value>,
+    fields: Vec<(String, Value)>,
+}
+
+/// Values of vim dictionaries/lists and Lua tables. Anything else, such as
+/// functions or expressions, is `Other`.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Num(i64),
+    Bool(bool),
+    Table(Table),
+    Other,
+}
+
+impl Value {
+    fn strings(&self) -> Vec<String> {
+        match *self {
+            Value::Str(ref s) => vec![s.clone()],
+            Value::Table(ref t) => t
+                .items
+                .iter()
+                .filter_map(|v| match *v {
+                    Value::Str(ref s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Plugin names of a dependency list, given as names or as nested specs.
+    fn specs(&self) -> Vec<String> {
+        match *self {
+            Value::Str(ref s) => vec![s.clone()],
+            Value::Table(ref t) => t
+                .items
+                .iter()
+                .filter_map(|v| match *v {
+                    Value::Str(ref s) => Some(s.clone()),
+                    Value::Table(ref t) => match t.items.first() {
+                        Some(Value::Str(ref s)) => Some(s.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Key mappings given as keys mapped in `modes`, as lazy.nvim `{ lhs, mode = ... }`
+    /// tables, packer `{ mode, lhs }` pairs or dein `{ mode: keys }` dictionaries.
+    fn key_maps(&self, modes: &[&str]) -> Vec<KeyMap> {
+        let valid = |m: &str| keymap::MODES.contains(&m);
+        let t = match *self {
+            Value::Str(ref s) => return vec![key_map(s, modes)],
+            Value::Table(ref t) => t,
+            _ => return vec![],
+        };
+        let mut ret = Vec::new();
+        for item in &t.items {
+            match *item {
+                Value::Str(ref s) => ret.push(key_map(s, modes)),
+                Value::Table(ref k) => {
+                    let strings = item.strings();
+                    let mode = k.fields.iter().find(|(f, _)| f == "mode");
+                    match (strings.as_slice(), mode) {
+                        ([lhs, ..], Some((_, m))) => {
+                            let m = m.strings();
+                            let m = m.iter().map(|x| x.as_str()).filter(|x| valid(x));
+                            ret.push(key_map(lhs, &m.collect::<Vec<&str>>()));
+                        }
+                        ([mode, lhs], None) if valid(mode) => ret.push(key_map(lhs, &[mode])),
+                        ([lhs, ..], None) => ret.push(key_map(lhs, modes)),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (mode, keys) in &t.fields {
+            let mode = mode.chars().map(|c| c.to_string()).collect::<Vec<String>>();
+            if mode.iter().all(|m| valid(m)) {
+                let mode = mode.iter().map(|m| m.as_str()).collect::<Vec<&str>>();
+                ret.extend(keys.strings().iter().map(|k| key_map(k, &mode)));
+            }
+        }
+        ret.retain(|k| !k.modes.is_empty());
+        ret
+    }
+
+    fn is_true(&self) -> bool {
+        match *self {
+            Value::Bool(b) => b,
+            Value::Num(n) => n != 0,
+            _ => false,
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Parser<'a> {
+        Parser { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&'a Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let tok = self.tokens.get(self.pos);
+        self.pos += 1;
+        tok
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Value {
+        match self.peek() {
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Value::Str(s.clone())
+            }
+            Some(Token::Ident(i)) if i == "true" || i == "v:true" => {
+                self.pos += 1;
+                Value::Bool(true)
+            }
+            Some(Token::Ident(i)) if i == "false" || i == "v:false" => {
+                self.pos += 1;
+                Value::Bool(false)
+            }
+            Some(Token::Ident(i)) if i.parse::<i64>().is_ok() => {
+                self.pos += 1;
+                Value::Num(i.parse().unwrap_or(0))
+            }
+            Some(Token::Punct('{')) => {
+                self.pos += 1;
+                Value::Table(self.table('}'))
+            }
+            Some(Token::Punct('[')) => {
+                self.pos += 1;
+                Value::Table(self.table(']'))
+            }
+            _ => {
+                self.skip_expr();
+                Value::Other
+            }
+        }
+    }
+
+    fn table(&mut self, close: char) -> Table {
+        let mut table = Table::default();
+        loop {
+            if self.peek().is_none() || self.eat(close) {
+                break;
+            }
+            if self.eat(',') || self.eat(';') {
+                continue;
+            }
+
+            let key = match (self.peek(), self.peek_at(1)) {
+                (Some(Token::Ident(k)), Some(Token::Punct('='))) => Some(k.clone()),
+                (Some(Token::Str(k)), Some(Token::Punct(':'))) => Some(k.clone()),
+                _ => None,
+            };
+            if let Some(k) = key {
+                self.pos += 2;
+                let v = self.value();
+                table.fields.push((k, v));
+            } else {
+                let v = self.value();
+                table.items.push(v);
+            }
+        }
+        table
+    }
+
+    /// Skip an expression up to the next separator at the same nesting level.
+    fn skip_expr(&mut self) {
+        let mut depth = 0;
+        let start = self.pos;
+        while let Some(tok) = self.peek() {
+            match *tok {
+                Token::Punct(',') | Token::Punct(';') if depth == 0 => break,
+                Token::Punct('}') | Token::Punct(']') | Token::Punct(')') if depth == 0 => break,
+                Token::Punct('{') | Token::Punct('[') | Token::Punct('(') => depth += 1,
+                Token::Punct('}') | Token::Punct(']') | Token::Punct(')') => depth -= 1,
+                Token::Ident(ref i) if i == "function" || i == "if" || i == "do" => depth += 1,
+                Token::Ident(ref i) if i == "end" => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        if self.pos == start && self.peek().is_some() {
+            // Never get stuck on a stray closing token.
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vim_plug() {
+        let data = "
+\" Plug 'commented/out'
+Plug 'tpope/vim-fugitive'
+Plug 'junegunn/fzf', { 'do': './install --all' }
+Plug 'scrooloose/nerdtree', { 'on':  ['NERDTreeToggle', '<Plug>NERDTree'] }
+Plug 'https://github.com/fatih/vim-go.git', {
+    \\ 'for': 'go' }
+";
+        let specs = parse(Format::VimPlug, data);
+        assert_eq!(specs.len(), 4);
+        assert_eq!(specs[0], Spec::new("tpope/vim-fugitive"));
+        assert_eq!(specs[1].build, Some("./install --all".to_string()));
+        assert_eq!(specs[2].on, vec!["NERDTreeToggle"]);
+        assert_eq!(
+            specs[2].keys,
+            vec![key_map("<Plug>NERDTree", &["n", "v", "o", "i"])]
+        );
+        assert_eq!(specs[3].types, vec!["go"]);
+
+        let pack = specs
+            .into_iter()
+            .nth(3)
+            .unwrap()
+            .into_package("lang")
+            .unwrap();
+        assert_eq!(pack.name, "fatih/vim-go");
+        assert!(pack.opt);
+    }
+
+    #[test]
+    fn parse_vundle() {
+        let data = "Plugin 'VundleVim/Vundle.vim'\nBundle 'tpope/vim-surround'\n";
+        let names: Vec<String> = parse(Format::Vundle, data)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["VundleVim/Vundle.vim", "tpope/vim-surround"]);
+    }
+
+    #[test]
+    fn parse_dein() {
+        let data = "call dein#add('Shougo/deoplete.nvim', {'lazy': 1, 'on_ft': ['python']})
+call dein#add('junegunn/vim-easy-align', {'on_map': {'nx': '<Plug>(EasyAlign)'}})
+call dein#add('junegunn/fzf', {'on_func': 'fzf#'})";
+        let specs = parse(Format::Dein, data);
+        assert_eq!(specs.len(), 3);
+        assert_eq!(
+            specs[1].keys,
+            vec![key_map("<Plug>(EasyAlign)", &["n", "x"])]
+        );
+        assert!(specs[0].opt);
+        assert_eq!(specs[0].types, vec!["python"]);
+        assert_eq!(specs[2].funcs, vec!["fzf#"]);
+    }
+
+    #[test]
+    fn parse_packer() {
+        let data = "
+return require('packer').startup(function(use)
+  -- use 'commented/out'
+  use 'wbthomas/packer.nvim'
+  use { 'tpope/vim-dispatch', opt = true, cmd = {'Dispatch', 'Make'} }
+  use {
+    'nvim-telescope/telescope.nvim',
+    requires = { {'nvim-lua/plenary.nvim'} },
+    module = 'telescope',
+    config = function() require('telescope').setup{} end,
+  }
+end)
+";
+        let specs = parse(Format::Lua, data);
+        let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "wbthomas/packer.nvim",
+                "tpope/vim-dispatch",
+                "nvim-telescope/telescope.nvim",
+                "nvim-lua/plenary.nvim"
+            ]
+        );
+        assert!(specs[1].opt);
+        assert_eq!(specs[1].on, vec!["Dispatch", "Make"]);
+        assert_eq!(specs[2].requires, vec!["nvim-lua/plenary.nvim"]);
+        assert_eq!(specs[2].modules, vec!["telescope"]);
+    }
+
+    #[test]
+    fn parse_lazy() {
+        let data = "
+return {
+  'folke/tokyonight.nvim',
+  { 'mattn/emmet-vim', ft = { 'html', 'css' }, build = 'make', event = 'InsertEnter' },
+  { 'junegunn/vim-easy-align', keys = { 'gl', { 'ga', mode = { 'n', 'x' } } } },
+  { 'nvim-treesitter/nvim-treesitter', build = ':TSUpdate', dependencies = 'x/y' },
+}
+";
+        let specs = parse(Format::Lua, data);
+        let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "folke/tokyonight.nvim",
+                "mattn/emmet-vim",
+                "junegunn/vim-easy-align",
+                "nvim-treesitter/nvim-treesitter",
+                "x/y"
+            ]
+        );
+        assert_eq!(
+            specs[2].keys,
+            vec![KeyMap::new("gl"), key_map("ga", &["n", "x"])]
+        );
+        assert_eq!(specs[1].types, vec!["html", "css"]);
+        assert_eq!(specs[1].build, Some("make".to_string()));
+        assert_eq!(specs[1].events, vec!["InsertEnter"]);
+        assert_eq!(specs[3].build, None);
+        assert_eq!(specs[3].vim_build, Some(":TSUpdate".to_string()));
+        assert_eq!(specs[3].requires, vec!["x/y"]);
+    }
+
+    #[test]
+    fn normalize_names() {
+        assert_eq!(
+            normalize_name("git@github.com:user/repo.git"),
+            Some(("user/repo".to_string(), false))
+        );
+        assert_eq!(normalize_name("https://gitlab.com/user/repo"), None);
+        assert_eq!(
+            normalize_name("/opt/plugin"),
+            Some(("/opt/plugin".to_string(), true))
+        );
+    }
+}
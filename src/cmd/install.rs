@@ -1,12 +1,12 @@
-use crate::git;
-use crate::package::{self, Package};
-use crate::task::{TaskManager, TaskType};
-use crate::{Error, Result};
+use pack::context::Context;
+use pack::echo;
+use pack::install::{self, Plugins};
+use pack::keymap::KeyMap;
+use pack::package;
+use pack::task::TaskType;
 
 use clap::{value_t, ArgMatches};
 use num_cpus;
-use std::os::unix::fs::symlink;
-use std::path::Path;
 
 #[derive(Debug)]
 struct InstallArgs {
@@ -14,40 +14,41 @@ struct InstallArgs {
     local: bool,
     on: Option<String>,
     for_: Option<String>,
+    keys: Option<String>,
+    events: Option<Vec<String>>,
     threads: Option<usize>,
     opt: bool,
-    category: String,
+    category: Option<String>,
     build: Option<String>,
+    requires: Option<String>,
 }
 
 impl InstallArgs {
     fn from_matches(m: &ArgMatches) -> InstallArgs {
         InstallArgs {
-            plugins: m.values_of_lossy("package").unwrap_or_else(|| vec![]),
+            plugins: m.values_of_lossy("package").unwrap_or_default(),
             local: m.is_present("local"),
             on: value_t!(m, "on", String).ok(),
             for_: value_t!(m, "for", String).ok(),
+            keys: value_t!(m, "keys", String).ok(),
+            events: m.values_of_lossy("event"),
             threads: value_t!(m, "threads", usize).ok(),
             opt: m.is_present("opt"),
-            category: value_t!(m, "category", String).unwrap_or_default(),
+            // The category has a default value, which shouldn't move packages of the
+            // packfile.
+            category: value_t!(m, "category", String)
+                .ok()
+                .filter(|_| m.occurrences_of("category") > 0),
             build: value_t!(m, "build", String).ok(),
+            requires: value_t!(m, "requires", String).ok(),
         }
     }
 }
 
-struct Plugins {
-    names: Vec<String>,
-    category: String,
-    opt: bool,
-    on: Option<String>,
-    types: Option<Vec<String>>,
-    build: Option<String>,
-    threads: usize,
-    local: bool,
-}
-
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
     let args = InstallArgs::from_matches(matches);
+    let output = super::output(matches);
+    let _lock = super::lock(&ctxs[0], output);
 
     let threads = match args.threads {
         Some(t) => t,
@@ -58,114 +59,50 @@ pub fn exec(matches: &ArgMatches) {
         die!("Threads should be greater than 0");
     }
 
-    let opt = args.on.is_some() || args.for_.is_some() || args.opt;
+    let opt = args.on.is_some()
+        || args.for_.is_some()
+        || args.keys.is_some()
+        || args.events.is_some()
+        || args.opt;
+    let on = args
+        .on
+        .map(|e| e.split(',').map(|e| e.to_string()).collect::<Vec<String>>());
     let types = args
         .for_
         .map(|e| e.split(',').map(|e| e.to_string()).collect::<Vec<String>>());
+    let keys = args
+        .keys
+        .map(|e| e.split(',').map(KeyMap::new).collect::<Vec<KeyMap>>());
+    let requires = args
+        .requires
+        .map(|e| e.split(',').map(|e| e.to_string()).collect::<Vec<String>>());
 
     let plugins = Plugins {
         names: args.plugins,
         category: args.category,
         opt,
-        on: args.on,
+        on,
         types,
+        keys,
+        events: args.events,
         build: args.build,
+        requires,
         threads,
         local: args.local,
     };
 
-    if let Err(e) = install_plugins(&plugins) {
-        die!("Err: {}", e);
-    }
-}
-
-fn install_plugins(plugins: &Plugins) -> Result<()> {
-    let mut packs = package::fetch()?;
-    {
-        let mut manager = TaskManager::new(TaskType::Install, plugins.threads);
-
-        if plugins.names.is_empty() {
-            for pack in &packs {
-                manager.add(pack.clone());
-            }
-        } else {
-            let targets = plugins.names.iter().map(|n| {
-                let mut p = Package::new(n, &plugins.category, plugins.opt);
-                p.local = if Path::new(n).is_dir() {
-                    true
-                } else {
-                    plugins.local
-                };
-                if let Some(ref c) = plugins.on {
-                    p.set_load_command(c);
-                }
-                if let Some(ref t) = plugins.types {
-                    p.set_types(t.clone());
-                }
-                if let Some(ref c) = plugins.build {
-                    p.set_build_command(c);
-                }
-                p
-            });
-            for mut pack in targets {
-                let having = match packs.iter_mut().find(|x| x.name == pack.name) {
-                    Some(x) => {
-                        if !x.is_installed() {
-                            x.set_category(pack.category.as_str());
-                            x.set_opt(pack.opt);
-                            x.set_types(pack.for_types.clone());
-
-                            x.load_command = pack.load_command.clone();
-                            x.build_command = pack.build_command.clone();
-                        } else {
-                            pack.set_category(x.category.as_str());
-                            pack.set_opt(x.opt);
-                        }
-                        true
-                    }
-                    None => false,
-                };
-                if !having {
-                    packs.push(pack.clone());
-                }
-                manager.add(pack);
-            }
-        }
-
-        for fail in manager.run(install_plugin) {
-            packs.retain(|e| e.name != fail);
+    for ctx in ctxs {
+        if let Err(e) = package::sync_includes(ctx, false) {
+            die!("Fail to clone included packfiles: {}", e);
         }
     }
 
-    packs.sort_by(|a, b| a.name.cmp(&b.name));
-
-    package::update_pack_plugin(&packs)?;
-    package::save(packs)
-}
-
-fn install_plugin(pack: &Package) -> (Result<()>, bool) {
-    let res = do_install(pack);
-    let status = match res {
-        Err(Error::PluginInstalled(_)) => true,
-        Err(_) => false,
-        _ => true,
+    let reporter = match echo::reporter(output) {
+        Ok(r) => r,
+        Err(e) => die!("{}", e),
     };
-    (res, status)
-}
 
-fn do_install(pack: &Package) -> Result<()> {
-    let path = pack.path();
-    if path.is_dir() {
-        Err(Error::plugin_installed(&path))
-    } else if pack.local {
-        let src = Path::new(&pack.name);
-        if !src.is_dir() {
-            Err(Error::NoPlugin)
-        } else {
-            symlink(&src, &path)?;
-            Ok(())
-        }
-    } else {
-        git::clone(&pack.name, &path)
-    }
+    super::sync_targets(ctxs, output, TaskType::Install, |ctx| {
+        install::install_plugins(ctx, &plugins, reporter.clone())
+    });
 }
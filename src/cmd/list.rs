@@ -1,5 +1,7 @@
-use crate::package::{self, Package};
-use crate::Result;
+use pack::context::Context;
+use pack::package::{self, Package};
+use pack::utils::json_string;
+use pack::Result;
 
 use clap::{value_t, ArgMatches};
 
@@ -9,6 +11,7 @@ struct ListArgs {
     opt: bool,
     detached: bool,
     category: Option<String>,
+    json: bool,
 }
 
 impl ListArgs {
@@ -18,57 +21,83 @@ impl ListArgs {
             opt: m.is_present("opt"),
             detached: m.is_present("detached"),
             category: value_t!(m, "category", String).ok(),
+            json: m.value_of("output") == Some("json"),
         }
     }
 }
 
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let ctx = &ctxs[0];
     let args = ListArgs::from_matches(matches);
 
-    if let Err(e) = list_packages(args) {
+    if let Err(e) = list_packages(ctx, args) {
         die!("Err: {}", e);
     }
 }
 
-fn list_packages(args: ListArgs) -> Result<()> {
+fn list_packages(ctx: &Context, args: ListArgs) -> Result<()> {
     let f = if args.detached {
         list_detached
     } else {
         list_installed
     };
-    f(&args.category, args.start, args.opt)
+    f(ctx, &args)
 }
 
-fn list_installed(category: &Option<String>, start: bool, opt: bool) -> Result<()> {
-    let packs = package::fetch()?;
+fn list_installed(ctx: &Context, args: &ListArgs) -> Result<()> {
+    let packs = package::fetch(ctx)?;
 
     let filter = |x: &Package| -> bool {
         let mut status = true;
-        if let Some(ref c) = *category {
+        if let Some(ref c) = args.category {
             status &= &x.category == c;
         }
-        if start {
+        if args.start {
             status &= !x.opt;
         }
-        if opt {
+        if args.opt {
             status &= x.opt;
         }
         status
     };
 
     for p in packs.into_iter().filter(filter) {
-        println!("{}", p);
+        if args.json {
+            println!(
+                "{{\"event\": \"package\", \"installed\": {}, \"package\": {}}}",
+                p.is_installed(ctx),
+                super::export::json_package(&p)
+            );
+        } else {
+            println!("{}", p);
+        }
     }
     Ok(())
 }
 
-fn list_detached(category: &Option<String>, start: bool, opt: bool) -> Result<()> {
-    let installed = package::fetch()?;
-    let pack_names: Vec<&str> = installed.iter().map(|p| p.repo().1).collect();
+fn list_detached(ctx: &Context, args: &ListArgs) -> Result<()> {
+    let installed = package::fetch(ctx)?;
+    let pack_names: Vec<&str> = installed.iter().map(|p| p.dir_name()).collect();
 
-    package::walk_packs(category, start, opt, |cate, option, name| {
-        if !pack_names.contains(&name) {
-            println!("{}/{}/{}", cate, option, name);
-        }
-    })
+    package::walk_packs(
+        ctx,
+        &args.category,
+        args.start,
+        args.opt,
+        |cate, option, name| {
+            if pack_names.contains(&name) {
+                return;
+            }
+            if args.json {
+                println!(
+                    "{{\"event\": \"detached\", \"name\": {}, \"category\": {}, \"opt\": {}}}",
+                    json_string(name),
+                    json_string(cate),
+                    option == "opt"
+                );
+            } else {
+                println!("{}/{}/{}", cate, option, name);
+            }
+        },
+    )
 }
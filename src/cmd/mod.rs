@@ -1,7 +1,103 @@
 pub mod config;
+pub mod export;
 pub mod generate;
+pub mod import;
 pub mod install;
 pub mod list;
 pub mod move_cmd;
+pub mod outdated;
+pub mod retry;
 pub mod uninstall;
 pub mod update;
+
+use pack::context::Context;
+use pack::echo::Output;
+use pack::retry::{self as last_run, LastRun};
+use pack::task::{Report, TaskType};
+use pack::utils::{json_string, FileLock};
+use pack::{Error, Result};
+
+use clap::ArgMatches;
+
+/// Lock the pack directory for the rest of a command modifying it.
+fn lock(ctx: &Context, output: Output) -> FileLock {
+    let on_wait = || message(output, "Waiting for another pack process to finish...");
+    match ctx.lock(on_wait) {
+        Ok(l) => l,
+        Err(e) => die!("Fail to lock pack directory: {}", e),
+    }
+}
+
+/// Print a message which isn't part of the progress. It goes to stderr with `--output
+/// json`, so that stdout only has JSON events.
+fn message(output: Output, msg: &str) {
+    if output == Output::Json {
+        eprintln!("{}", msg);
+    } else {
+        println!("{}", msg);
+    }
+}
+
+/// Exit status when some packages failed to sync.
+const EXIT_PARTIAL_FAILURE: i32 = 2;
+/// Exit status when no package could be synced.
+const EXIT_FAILURE: i32 = 3;
+
+/// How the progress is printed, given by `--output` and `--no-tty`.
+fn output(m: &ArgMatches) -> Output {
+    if m.value_of("output") == Some("json") {
+        Output::Json
+    } else if m.is_present("no-tty") {
+        Output::Plain
+    } else {
+        Output::Terminal
+    }
+}
+
+/// Sync the packages of every target with `sync`, naming the targets when there are
+/// several. A target without packages to sync doesn't stop the others. The failed
+/// packages are recorded for `pack retry`, and the process exits with
+/// `EXIT_PARTIAL_FAILURE` or `EXIT_FAILURE` when there are any.
+fn sync_targets<F>(ctxs: &[Context], output: Output, task_type: TaskType, sync: F)
+where
+    F: Fn(&Context) -> Result<Report>,
+{
+    let json = output == Output::Json;
+    let mut run = LastRun {
+        task_type,
+        failures: Vec::new(),
+    };
+    let mut done = 0;
+    for ctx in ctxs {
+        if ctxs.len() > 1 {
+            if json {
+                let name = json_string(ctx.target.name());
+                println!("{{\"event\": \"target\", \"name\": {}}}", name);
+            } else {
+                println!("Target {}:", ctx.target.name());
+            }
+        }
+        match sync(ctx) {
+            Ok(report) => {
+                done += report.done();
+                for pack in report.failures() {
+                    run.failures.push((ctx.target, pack));
+                }
+            }
+            Err(Error::NoTask) if ctxs.len() > 1 && json => {}
+            Err(Error::NoTask) if ctxs.len() > 1 => println!("{}", Error::NoTask),
+            Err(e) => die!("Err: {}", e),
+        }
+    }
+
+    if let Err(e) = last_run::save(&ctxs[0], &run) {
+        eprintln!("Fail to record failed packages: {}", e);
+    }
+    if !run.failures.is_empty() {
+        std::process::exit(if done == 0 {
+            EXIT_FAILURE
+        } else {
+            EXIT_PARTIAL_FAILURE
+        });
+    }
+}
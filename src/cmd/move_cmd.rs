@@ -1,6 +1,7 @@
-use crate::package;
-use crate::utils;
-use crate::{Error, Result};
+use pack::context::Context;
+use pack::package;
+use pack::utils;
+use pack::{Error, Result};
 
 use clap::{value_t, ArgMatches};
 use std::fs;
@@ -22,42 +23,86 @@ impl MoveArgs {
     }
 }
 
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
     let args = MoveArgs::from_matches(matches);
+    let _lock = super::lock(&ctxs[0], super::output(matches));
 
-    if let Err(e) = move_plugin(&args.plugin, &args.category, args.opt) {
+    if let Err(e) = move_plugin(ctxs, &args.plugin, &args.category, args.opt) {
         die!("{}", e);
     }
 }
 
-fn move_plugin(plugin: &str, category: &str, opt: bool) -> Result<()> {
-    let mut packs = package::fetch()?;
-    let changed = {
-        let pack = match packs.iter_mut().find(|p| p.name == plugin) {
-            Some(p) => p,
-            None => return Err(Error::PluginNotInstalled),
-        };
+fn move_plugin(ctxs: &[Context], plugin: &str, category: &str, opt: bool) -> Result<()> {
+    let mut file = package::load(&ctxs[0])?;
+    let packs = &mut file.packs;
+    let pack = match packs.iter_mut().find(|p| p.name == plugin) {
+        Some(p) => p,
+        None => return Err(Error::PluginNotInstalled),
+    };
+
+    // The moved package keeps the rest of its entry, like its conditions.
+    let mut moved = pack.clone();
+    moved.set_category(category);
+    moved.set_opt(opt);
 
-        let origin_path = pack.path();
+    let mut installed = false;
+    for ctx in ctxs {
+        let origin_path = pack.path(ctx);
         if !origin_path.is_dir() {
-            return Err(Error::PluginNotInstalled);
+            continue;
         }
+        installed = true;
 
-        let path = package::Package::new(plugin, category, opt).path();
+        let path = moved.path(ctx);
         if origin_path != path {
             utils::copy_directory(&origin_path, &path)?;
             fs::remove_dir_all(&origin_path)?;
-            pack.set_category(category as &str);
-            pack.set_opt(opt);
-            true
-        } else {
-            false
         }
-    };
+    }
+    if !installed {
+        return Err(Error::PluginNotInstalled);
+    }
 
-    if changed {
+    if *pack != moved {
+        *pack = moved;
         packs.sort_by(|a, b| a.name.cmp(&b.name));
-        package::save(packs)?;
+        for ctx in ctxs {
+            package::update_pack_plugin(ctx, packs)?;
+        }
+        file.save(&ctxs[0])?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pack::package::Package;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn move_keeps_entry() {
+        let dir = env::temp_dir().join(format!("pack-move-{}", process::id()));
+        let ctxs = [Context::new(&dir, None)];
+        let ctx = &ctxs[0];
+        let src = dir.join("src/my-plugin");
+        let mut pack = Package::new(&src.to_string_lossy(), "default", false);
+        pack.local = true;
+        pack.when.has.push(String::from("python3"));
+        fs::create_dir_all(pack.path(ctx).join("plugin")).unwrap();
+        package::save(ctx, vec![pack.clone()]).unwrap();
+
+        move_plugin(&ctxs, &pack.name, "lang", true).unwrap();
+        let packs = package::fetch(ctx).unwrap();
+        let path = dir.join("pack/lang/opt/my-plugin/plugin").is_dir();
+        let origin = pack.path(ctx).exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        pack.set_category("lang");
+        pack.set_opt(true);
+        assert_eq!(packs, vec![pack]);
+        assert!(path);
+        assert!(!origin);
+    }
+}
@@ -0,0 +1,42 @@
+use pack::context::Context;
+use pack::echo;
+use pack::task::TaskType;
+use pack::update;
+
+use clap::{value_t, ArgMatches};
+
+#[derive(Debug)]
+struct OutdatedArgs {
+    plugins: Vec<String>,
+    threads: Option<usize>,
+}
+
+impl OutdatedArgs {
+    fn from_matches(m: &ArgMatches) -> OutdatedArgs {
+        OutdatedArgs {
+            plugins: m.values_of_lossy("package").unwrap_or_default(),
+            threads: value_t!(m, "threads", usize).ok(),
+        }
+    }
+}
+
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let args = OutdatedArgs::from_matches(matches);
+    let output = super::output(matches);
+    // The failures are recorded for `pack retry`.
+    let _lock = super::lock(&ctxs[0], output);
+
+    let threads = args.threads.unwrap_or_else(num_cpus::get);
+    if threads < 1 {
+        die!("Threads should be greater than 0");
+    }
+
+    let reporter = match echo::reporter(output) {
+        Ok(r) => r,
+        Err(e) => die!("{}", e),
+    };
+
+    super::sync_targets(ctxs, output, TaskType::Outdated, |ctx| {
+        update::outdated_plugins(ctx, &args.plugins, threads, reporter.clone())
+    });
+}
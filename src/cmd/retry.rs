@@ -0,0 +1,68 @@
+use pack::context::Context;
+use pack::echo;
+use pack::install;
+use pack::package;
+use pack::retry;
+use pack::task::TaskType;
+use pack::update;
+use pack::Error;
+
+use clap::{value_t, ArgMatches};
+
+#[derive(Debug)]
+struct RetryArgs {
+    threads: Option<usize>,
+}
+
+impl RetryArgs {
+    fn from_matches(m: &ArgMatches) -> RetryArgs {
+        RetryArgs {
+            threads: value_t!(m, "threads", usize).ok(),
+        }
+    }
+}
+
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
+    let args = RetryArgs::from_matches(matches);
+    let output = super::output(matches);
+    let _lock = super::lock(&ctxs[0], output);
+
+    let threads = args.threads.unwrap_or_else(num_cpus::get);
+    if threads < 1 {
+        die!("Threads should be greater than 0");
+    }
+
+    let run = match retry::load(&ctxs[0]) {
+        Ok(Some(r)) if !r.failures.is_empty() => r,
+        Ok(_) => {
+            super::message(output, "No failed packages to retry.");
+            return;
+        }
+        Err(e) => die!("Fail to read failed packages: {}", e),
+    };
+
+    for ctx in ctxs {
+        if let Err(e) = package::sync_includes(ctx, false) {
+            die!("Fail to clone included packfiles: {}", e);
+        }
+    }
+
+    let reporter = match echo::reporter(output) {
+        Ok(r) => r,
+        Err(e) => die!("{}", e),
+    };
+
+    super::sync_targets(ctxs, output, run.task_type, |ctx| {
+        let packs = run.failures_of(ctx.target);
+        // Without names, every package would be synced.
+        if packs.is_empty() {
+            return Err(Error::NoTask);
+        }
+        let names: Vec<String> = packs.iter().map(|p| p.name.clone()).collect();
+        match run.task_type {
+            TaskType::Install => install::retry_plugins(ctx, &packs, threads, reporter.clone()),
+            TaskType::Update => update::update_plugins(ctx, &names, threads, &[], reporter.clone()),
+            TaskType::Outdated => update::outdated_plugins(ctx, &names, threads, reporter.clone()),
+        }
+    });
+}
@@ -1,5 +1,6 @@
-use crate::package::{self, Package};
-use crate::Result;
+use pack::context::Context;
+use pack::package::{self, Package};
+use pack::Result;
 
 use clap::ArgMatches;
 use std::fs;
@@ -13,43 +14,99 @@ struct UninstallArgs {
 impl UninstallArgs {
     fn from_matches(m: &ArgMatches) -> UninstallArgs {
         UninstallArgs {
-            plugins: m.values_of_lossy("package").unwrap_or_else(|| vec![]),
+            plugins: m.values_of_lossy("package").unwrap_or_default(),
             all: m.is_present("all"),
         }
     }
 }
 
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
     let args = UninstallArgs::from_matches(matches);
+    let _lock = super::lock(&ctxs[0], super::output(matches));
 
-    if let Err(e) = uninstall_plugins(&args.plugins, args.all) {
+    if let Err(e) = uninstall_plugins(ctxs, &args.plugins, args.all) {
         die!("{}", e);
     }
 }
 
-fn uninstall_plugins(plugins: &[String], all: bool) -> Result<()> {
-    let mut packs = package::fetch()?;
+fn uninstall_plugins(ctxs: &[Context], plugins: &[String], all: bool) -> Result<()> {
+    let mut file = package::load(&ctxs[0])?;
+    let packs = &mut file.packs;
+    let mut others = Vec::new();
+    for ctx in ctxs {
+        for other in ctx.other_profiles()? {
+            let packs = package::fetch(&other)?;
+            others.push((other, packs));
+        }
+    }
 
     for pack in packs.iter().filter(|p| plugins.contains(&p.name)) {
-        uninstall_plugin(pack, all)?;
+        let dependents = package::dependents(&pack.name, packs)
+            .into_iter()
+            .filter(|x| !plugins.iter().any(|p| p == x))
+            .collect::<Vec<&str>>();
+        if !dependents.is_empty() {
+            println!(
+                "Warning: {} is required by {}",
+                pack.name,
+                dependents.join(", ")
+            );
+        }
+        for ctx in ctxs {
+            let others = others
+                .iter()
+                .filter(|(c, _)| c.target == ctx.target)
+                .collect::<Vec<_>>();
+            uninstall_plugin(ctx, pack, all, &others)?;
+        }
     }
 
     packs.retain(|x| !plugins.contains(&x.name));
     packs.sort_by(|a, b| a.name.cmp(&b.name));
-    package::update_pack_plugin(&packs)?;
-    package::save(packs)?;
+    for ctx in ctxs {
+        package::update_pack_plugin(ctx, packs)?;
+    }
+    file.save(&ctxs[0])?;
     Ok(())
 }
 
-fn uninstall_plugin(plugin: &Package, all: bool) -> Result<()> {
-    let config_file = plugin.config_path();
-    let plugin_path = plugin.path();
+/// Remove the directory and, with `all`, the configs of `plugin`, unless a package of
+/// another profile in `others` uses them.
+fn uninstall_plugin(
+    ctx: &Context,
+    plugin: &Package,
+    all: bool,
+    others: &[&(Context, Vec<Package>)],
+) -> Result<()> {
+    let plugin_path = plugin.path(ctx);
+
+    let users = |used: &dyn Fn(&Context, &Package) -> bool| {
+        others
+            .iter()
+            .filter(|(c, packs)| packs.iter().any(|p| used(c, p)))
+            .map(|(c, _)| c.profile_name())
+            .collect::<Vec<&str>>()
+    };
+    let path_users = users(&|c, p| p.path(c) == plugin_path);
+    if !path_users.is_empty() {
+        println!(
+            "Warning: {} is kept, it is used by profile {}",
+            plugin_path.display(),
+            path_users.join(", ")
+        );
+    }
+    let config_users = users(&|_, p| p.name == plugin.name);
 
-    if config_file.is_file() && all {
-        fs::remove_file(&config_file)?;
+    if all && config_users.is_empty() {
+        let files = plugin.config_files(ctx, false);
+        for config_file in files.iter().chain(&plugin.config_files(ctx, true)) {
+            if config_file.is_file() {
+                fs::remove_file(config_file)?;
+            }
+        }
     }
 
-    if plugin_path.is_dir() {
+    if plugin_path.is_dir() && path_users.is_empty() {
         fs::remove_dir_all(&plugin_path)?;
     }
 
@@ -1,7 +1,9 @@
-use crate::git;
-use crate::package::{self, Package};
-use crate::task::{TaskManager, TaskType};
-use crate::{Error, Result};
+use pack::context::Context;
+use pack::echo;
+use pack::package;
+use pack::task::TaskType;
+use pack::update;
+
 use clap::{value_t, ArgMatches};
 
 #[derive(Debug)]
@@ -15,20 +17,25 @@ struct UpdateArgs {
 impl UpdateArgs {
     fn from_matches(m: &ArgMatches) -> UpdateArgs {
         UpdateArgs {
-            plugins: m.values_of_lossy("package").unwrap_or_else(|| vec![]),
-            skip: m.values_of_lossy("skip").unwrap_or_else(|| vec![]),
+            plugins: m.values_of_lossy("package").unwrap_or_default(),
+            skip: m.values_of_lossy("skip").unwrap_or_default(),
             threads: value_t!(m, "threads", usize).ok(),
             packfile: m.is_present("packfile"),
         }
     }
 }
 
-pub fn exec(matches: &ArgMatches) {
+pub fn exec(ctxs: &[Context], matches: &ArgMatches) {
     let args = UpdateArgs::from_matches(matches);
+    let output = super::output(matches);
+    let _lock = super::lock(&ctxs[0], output);
 
     if args.packfile {
-        if let Err(e) = update_packfile() {
-            die!("Err: {}", e);
+        super::message(output, "Update _pack file for all plugins.");
+        for ctx in ctxs {
+            if let Err(e) = package::generate(ctx) {
+                die!("Err: {}", e);
+            }
         }
         return;
     }
@@ -38,67 +45,19 @@ pub fn exec(matches: &ArgMatches) {
         die!("Threads should be greater than 0");
     }
 
-    if let Err(e) = update_plugins(&args.plugins, threads, &args.skip) {
-        die!("Err: {}", e);
-    }
-}
-
-fn update_packfile() -> Result<()> {
-    println!("Update _pack file for all plugins.");
-    let mut packs = package::fetch()?;
-
-    packs.sort_by(|a, b| a.name.cmp(&b.name));
-    package::update_pack_plugin(&packs)?;
-
-    Ok(())
-}
-
-fn update_plugins(plugins: &[String], threads: usize, skip: &[String]) -> Result<()> {
-    let mut packs = package::fetch()?;
-
-    let mut manager = TaskManager::new(TaskType::Update, threads);
-    if plugins.is_empty() {
-        for pack in &packs {
-            if skip.iter().any(|x| pack.name.contains(x)) {
-                println!("Skip {}", pack.name);
-                continue;
-            }
-            manager.add(pack.clone());
-        }
-    } else {
-        for pack in packs.iter().filter(|x| plugins.contains(&x.name)) {
-            manager.add(pack.clone());
+    for ctx in ctxs {
+        if let Err(e) = package::sync_includes(ctx, true) {
+            let msg = format!("Fail to update included packfiles: {}", e);
+            super::message(output, &msg);
         }
     }
 
-    for fail in manager.run(update_plugin) {
-        packs.retain(|e| e.name != fail);
-    }
-
-    packs.sort_by(|a, b| a.name.cmp(&b.name));
-
-    package::update_pack_plugin(&packs)?;
-
-    Ok(())
-}
-
-fn update_plugin(pack: &Package) -> (Result<()>, bool) {
-    let res = do_update(pack);
-    let status = match res {
-        Err(Error::SkipLocal) | Err(Error::Git(_)) => true,
-        Err(_) => false,
-        _ => true,
+    let reporter = match echo::reporter(output) {
+        Ok(r) => r,
+        Err(e) => die!("{}", e),
     };
-    (res, status)
-}
 
-fn do_update(pack: &Package) -> Result<()> {
-    let path = pack.path();
-    if !path.is_dir() {
-        Err(Error::PluginNotInstalled)
-    } else if pack.local {
-        Err(Error::SkipLocal)
-    } else {
-        git::update(&pack.name, &path)
-    }
+    super::sync_targets(ctxs, output, TaskType::Update, |ctx| {
+        update::update_plugins(ctx, &args.plugins, threads, &args.skip, reporter.clone())
+    });
 }
@@ -0,0 +1,188 @@
+use crate::context::Target;
+use crate::utils;
+use crate::{Error, Result};
+
+use std::env;
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// Conditions under which a package is used.
+///
+/// `host`, `os` and `env` are checked when syncing packages. `editor` and `has` can only be
+/// known by the running editor, so they guard the generated plugin file instead. `editor`
+/// also selects the targets a package is installed for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Condition {
+    /// `vim` or `neovim`
+    pub editor: Option<String>,
+    pub host: Vec<String>,
+    pub os: Vec<String>,
+    /// `VAR` requires the variable to be set, `VAR=value` requires that value
+    pub env: Vec<String>,
+    /// Vim features, as tested with `has()`
+    pub has: Vec<String>,
+}
+
+fn to_yaml(items: &[String]) -> Yaml {
+    if items.len() == 1 {
+        Yaml::from_str(&items[0])
+    } else {
+        Yaml::Array(items.iter().map(|e| Yaml::from_str(e)).collect())
+    }
+}
+
+impl Condition {
+    pub fn from_yaml(doc: &Yaml) -> Result<Condition> {
+        if doc.is_badvalue() {
+            return Ok(Condition::default());
+        }
+        let editor = match doc["editor"].as_str() {
+            Some(e @ "vim") | Some(e @ "neovim") => Some(e.to_string()),
+            Some(_) => return Err(Error::Format),
+            None => None,
+        };
+        Ok(Condition {
+            editor,
+            host: utils::string_list(&doc["host"])?,
+            os: utils::string_list(&doc["os"])?,
+            env: utils::string_list(&doc["env"])?,
+            has: utils::string_list(&doc["has"])?,
+        })
+    }
+
+    pub fn into_yaml(self) -> Yaml {
+        let mut doc = Hash::new();
+        if let Some(e) = self.editor {
+            doc.insert(Yaml::from_str("editor"), Yaml::String(e));
+        }
+        for (key, items) in [
+            ("host", &self.host),
+            ("os", &self.os),
+            ("env", &self.env),
+            ("has", &self.has),
+        ] {
+            if !items.is_empty() {
+                doc.insert(Yaml::from_str(key), to_yaml(items));
+            }
+        }
+        Yaml::Hash(doc)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.editor.is_none() && !self.is_static() && self.has.is_empty()
+    }
+
+    fn is_static(&self) -> bool {
+        !self.host.is_empty() || !self.os.is_empty() || !self.env.is_empty()
+    }
+
+    /// Whether the condition has to be checked by the editor.
+    pub fn is_runtime(&self) -> bool {
+        self.editor.is_some() || !self.has.is_empty()
+    }
+
+    /// Whether the package is used by the editor `target`.
+    pub fn matches_target(&self, target: Target) -> bool {
+        self.editor.as_deref().is_none_or(|e| e == target.name())
+    }
+
+    /// Check the conditions known when syncing packages.
+    pub fn matches_system(&self) -> bool {
+        let host_ok =
+            self.host.is_empty() || utils::hostname().is_some_and(|h| self.host.contains(&h));
+        let os_ok = self.os.is_empty() || self.os.iter().any(|x| is_current_os(x));
+        let env_ok = self.env.iter().all(|e| match e.split_once('=') {
+            Some((var, value)) => env::var(var).is_ok_and(|v| v == value),
+            None => env::var(e).is_ok_and(|v| !v.is_empty()),
+        });
+        host_ok && os_ok && env_ok
+    }
+
+    /// Vim script expression checking the runtime conditions.
+    pub fn vim_expr(&self) -> Option<String> {
+        let mut exprs = Vec::new();
+        match self.editor.as_deref() {
+            Some("vim") => exprs.push(String::from("!has('nvim')")),
+            Some(_) => exprs.push(String::from("has('nvim')")),
+            None => {}
+        }
+        for f in &self.has {
+            exprs.push(format!("has('{}')", f.replace('\'', "''")));
+        }
+        if exprs.is_empty() {
+            None
+        } else {
+            Some(exprs.join(" && "))
+        }
+    }
+
+    /// Lua expression checking the runtime conditions.
+    pub fn lua_expr(&self) -> Option<String> {
+        let mut exprs = Vec::new();
+        match self.editor.as_deref() {
+            Some("vim") => exprs.push(String::from("vim.fn.has('nvim') == 0")),
+            Some(_) => exprs.push(String::from("vim.fn.has('nvim') == 1")),
+            None => {}
+        }
+        for f in &self.has {
+            exprs.push(format!("vim.fn.has({}) == 1", utils::lua_string(f)));
+        }
+        if exprs.is_empty() {
+            None
+        } else {
+            Some(exprs.join(" and "))
+        }
+    }
+}
+
+fn is_current_os(os: &str) -> bool {
+    let os = match os {
+        "darwin" | "mac" | "osx" => "macos",
+        _ => os,
+    };
+    os == env::consts::OS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn parse(s: &str) -> Condition {
+        let docs = YamlLoader::load_from_str(s).unwrap();
+        Condition::from_yaml(&docs[0]).unwrap()
+    }
+
+    #[test]
+    fn parse_condition() {
+        let cond = parse("{editor: neovim, os: [linux, macos], has: python3}");
+        assert_eq!(cond.editor.as_deref(), Some("neovim"));
+        assert_eq!(cond.os, vec!["linux", "macos"]);
+        assert_eq!(cond.has, vec!["python3"]);
+        assert!(cond.is_runtime());
+        assert_eq!(
+            cond.vim_expr().as_deref(),
+            Some("has('nvim') && has('python3')")
+        );
+        assert_eq!(
+            Condition::from_yaml(&cond.clone().into_yaml()).unwrap(),
+            cond
+        );
+    }
+
+    #[test]
+    fn system_conditions() {
+        let cond = Condition {
+            os: vec![env::consts::OS.to_string()],
+            env: vec![String::from("PACK_TEST_UNSET_VARIABLE")],
+            ..Default::default()
+        };
+        assert!(!cond.matches_system());
+        let cond = Condition {
+            os: vec![env::consts::OS.to_string()],
+            ..Default::default()
+        };
+        assert!(cond.matches_system());
+        assert!(cond.vim_expr().is_none());
+    }
+}
@@ -0,0 +1,437 @@
+use crate::utils;
+use crate::{Error, Result};
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Editor packages are installed for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// Vim 8, configured under `~/.vim`
+    Vim,
+    /// Neovim, configured under `stdpath('config')` with packages under
+    /// `stdpath('data')/site`
+    Neovim,
+}
+
+impl Target {
+    pub fn from_name(name: &str) -> Option<Target> {
+        match name {
+            "vim" => Some(Target::Vim),
+            "neovim" | "nvim" => Some(Target::Neovim),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Vim => "vim",
+            Target::Neovim => "neovim",
+        }
+    }
+
+    /// Vim is used when `VIM_CONFIG_PATH` or `~/.vim` exists, neovim when only its config
+    /// directory exists.
+    pub fn detect() -> Target {
+        if env::var_os("VIM_CONFIG_PATH").is_some() {
+            return Target::Vim;
+        }
+        match dirs::home_dir() {
+            Some(ref home) if home.join(".vim").is_dir() => Target::Vim,
+            Some(ref home)
+                if xdg_dir("XDG_CONFIG_HOME", home, ".config")
+                    .join("nvim")
+                    .is_dir() =>
+            {
+                Target::Neovim
+            }
+            _ => Target::Vim,
+        }
+    }
+
+    /// Neovim is used for a config directory `root` with an `init.lua` or `init.vim`,
+    /// vim otherwise.
+    pub fn detect_in(root: &Path) -> Target {
+        if root.join("init.lua").is_file() || root.join("init.vim").is_file() {
+            Target::Neovim
+        } else {
+            Target::Vim
+        }
+    }
+}
+
+fn xdg_dir(var: &str, home: &Path, default: &str) -> PathBuf {
+    match env::var_os(var) {
+        Some(ref p) if Path::new(p).is_absolute() => PathBuf::from(p),
+        _ => home.join(default),
+    }
+}
+
+fn home_dir() -> Result<PathBuf> {
+    dirs::home_dir().ok_or_else(|| Error::Environment(String::from("No home directory found")))
+}
+
+/// Settings overriding the environment when creating a `Context`.
+#[derive(Debug, Clone, Default)]
+pub struct Options<'a> {
+    pub root: Option<&'a str>,
+    pub profile: Option<&'a str>,
+    pub packfile: Option<&'a str>,
+    pub targets: Vec<&'a str>,
+}
+
+/// Locations pack operates on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+    pub target: Target,
+    /// Editor config directory, `~/.vim` by default
+    pub base_dir: PathBuf,
+    /// Directory packages are installed to
+    pub pack_dir: PathBuf,
+    /// Directory of the packfile, package configs and pack's own state
+    pub config_dir: PathBuf,
+    pub packfile: PathBuf,
+    /// Directory the generated plugin file is written to
+    pub plugin_dir: PathBuf,
+    pub plugin_file: PathBuf,
+    /// Named profile, `None` for the default profile
+    pub profile: Option<String>,
+}
+
+impl Context {
+    /// Context for the vim config directory `base_dir`. The packfile and the generated
+    /// plugin file depend on `profile`, which `contexts` checks with `is_valid_profile`.
+    pub fn new<P: Into<PathBuf>>(base_dir: P, profile: Option<&str>) -> Context {
+        let base_dir = base_dir.into();
+        Context::layout(Target::Vim, base_dir.clone(), base_dir, profile)
+    }
+
+    /// Context for the neovim config directory `base_dir`, installing packages under
+    /// `site_dir/pack`. The generated plugin file is a Lua script.
+    pub fn neovim<P: Into<PathBuf>, Q: Into<PathBuf>>(
+        base_dir: P,
+        site_dir: Q,
+        profile: Option<&str>,
+    ) -> Context {
+        Context::layout(Target::Neovim, base_dir.into(), site_dir.into(), profile)
+    }
+
+    fn layout(
+        target: Target,
+        base_dir: PathBuf,
+        site_dir: PathBuf,
+        profile: Option<&str>,
+    ) -> Context {
+        let profile = profile
+            .filter(|p| !p.is_empty() && *p != DEFAULT_PROFILE)
+            .map(|p| p.to_string());
+        let config_dir = base_dir.join(".pack");
+        let plugin_dir = base_dir.join("plugin");
+        let mut ctx = Context {
+            target,
+            pack_dir: site_dir.join("pack"),
+            base_dir,
+            config_dir,
+            packfile: PathBuf::new(),
+            plugin_dir,
+            plugin_file: PathBuf::new(),
+            profile,
+        };
+        ctx.set_profile_files();
+        ctx
+    }
+
+    /// Set the packfile and the generated plugin file of the profile.
+    fn set_profile_files(&mut self) {
+        let ext = match self.target {
+            Target::Vim => "vim",
+            Target::Neovim => "lua",
+        };
+        let (packfile, plugin_file) = match self.profile {
+            Some(ref p) => (
+                self.config_dir.join("profiles").join(p).join("packfile"),
+                self.plugin_dir.join(format!("_pack_{}.{}", p, ext)),
+            ),
+            None => (
+                self.config_dir.join("packfile"),
+                self.plugin_dir.join(format!("_pack.{}", ext)),
+            ),
+        };
+        self.packfile = packfile;
+        self.plugin_file = plugin_file;
+    }
+
+    /// Contexts of the other profiles with a packfile. They install packages to the same
+    /// pack directory and share the package configs.
+    pub fn other_profiles(&self) -> Result<Vec<Context>> {
+        let mut profiles = vec![None];
+        let dir = self.config_dir.join("profiles");
+        if dir.is_dir() {
+            for entry in fs::read_dir(dir)? {
+                if let Ok(name) = entry?.file_name().into_string() {
+                    if is_valid_profile(&name) {
+                        profiles.push(Some(name));
+                    }
+                }
+            }
+        }
+        let mut ret = Vec::new();
+        for profile in profiles.into_iter().filter(|p| *p != self.profile) {
+            let mut ctx = self.clone();
+            ctx.profile = profile;
+            ctx.set_profile_files();
+            if ctx.packfile.is_file() {
+                ret.push(ctx);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Context from `PACK_TARGET`, `VIM_CONFIG_PATH`, `PACK_PROFILE` and `PACK_FILE`.
+    /// Without `PACK_TARGET` the editor is detected. Without `VIM_CONFIG_PATH`, vim uses
+    /// `~/.vim` and neovim uses its XDG directories.
+    pub fn from_env() -> Result<Context> {
+        Context::with_options(&Options::default())
+    }
+
+    /// Like `from_env`, with the given options taking precedence over the environment.
+    /// Only the first of several targets is used.
+    pub fn with_options(opts: &Options) -> Result<Context> {
+        Ok(contexts(opts)?.remove(0))
+    }
+
+    /// Use the packfile at `path` instead of the one under the config directory.
+    pub fn with_packfile<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.packfile = path.into();
+        self
+    }
+
+    /// Share the packfile and package configs of `primary`.
+    fn share_config(mut self, primary: &Context) -> Context {
+        self.config_dir = primary.config_dir.clone();
+        self.packfile = primary.packfile.clone();
+        self
+    }
+
+    pub fn profile_name(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+
+    /// File recording the packages which failed in the last install or update.
+    pub fn last_run_file(&self) -> PathBuf {
+        match self.profile {
+            Some(ref p) => self.config_dir.join("profiles").join(p).join("last-run"),
+            None => self.config_dir.join("last-run"),
+        }
+    }
+
+    /// Lock the pack directory against other pack processes modifying packages,
+    /// packfiles or generated files. `on_wait` is called if another process holds it.
+    pub fn lock<F: FnOnce()>(&self, on_wait: F) -> Result<utils::FileLock> {
+        if !self.config_dir.is_dir() {
+            fs::create_dir_all(&self.config_dir)?;
+        }
+        utils::FileLock::acquire(self.config_dir.join(".lock"), on_wait)
+    }
+}
+
+/// Contexts of every target given by the options or `PACK_TARGET`, as a comma separated
+/// list. The first target owns the packfile and the package configs, which the other
+/// targets share, and it is the only one `root` applies to.
+pub fn contexts(opts: &Options) -> Result<Vec<Context>> {
+    contexts_with(opts, |name| env::var_os(name))
+}
+
+/// Like `contexts`, reading the pack variables with `var` instead of from the environment.
+fn contexts_with<F>(opts: &Options, var: F) -> Result<Vec<Context>>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let string_var = |name| var(name).map(|v| v.to_string_lossy().into_owned());
+    let names: Vec<String> = if opts.targets.is_empty() {
+        string_var("PACK_TARGET")
+            .map(|t| t.split(',').map(|x| x.trim().to_string()).collect())
+            .unwrap_or_default()
+    } else {
+        opts.targets.iter().map(|t| t.to_string()).collect()
+    };
+    let mut targets = Vec::new();
+    for name in names.iter().filter(|x| !x.is_empty()) {
+        let t = Target::from_name(name)
+            .ok_or_else(|| Error::Environment(format!("Unknown target: {}", name)))?;
+        if !targets.contains(&t) {
+            targets.push(t);
+        }
+    }
+    if targets.is_empty() {
+        targets.push(match opts.root {
+            Some(root) => Target::detect_in(Path::new(root)),
+            None => Target::detect(),
+        });
+    }
+
+    let mut root = opts
+        .root
+        .map(PathBuf::from)
+        .or_else(|| var("VIM_CONFIG_PATH").map(PathBuf::from));
+    let profile = opts
+        .profile
+        .map(String::from)
+        .or_else(|| string_var("PACK_PROFILE"));
+    let profile = profile.as_deref();
+    // An empty profile selects the default one.
+    if let Some(p) = profile.filter(|p| !p.is_empty() && !is_valid_profile(p)) {
+        return Err(Error::Environment(format!("Invalid profile name: {}", p)));
+    }
+
+    let mut ret: Vec<Context> = Vec::with_capacity(targets.len());
+    for target in targets {
+        let ctx = match (target, root.take()) {
+            (Target::Vim, Some(root)) => Context::new(root, profile),
+            (Target::Vim, None) => Context::new(home_dir()?.join(".vim"), profile),
+            // Neovim also searches its config directory for packages.
+            (Target::Neovim, Some(root)) => Context::neovim(root.clone(), root, profile),
+            (Target::Neovim, None) => {
+                let home = home_dir()?;
+                Context::neovim(
+                    xdg_dir("XDG_CONFIG_HOME", &home, ".config").join("nvim"),
+                    xdg_dir("XDG_DATA_HOME", &home, ".local/share").join("nvim/site"),
+                    profile,
+                )
+            }
+        };
+        let ctx = match ret.first() {
+            Some(primary) => ctx.share_config(primary),
+            None => match opts
+                .packfile
+                .map(String::from)
+                .or_else(|| string_var("PACK_FILE"))
+            {
+                Some(p) => ctx.with_packfile(p),
+                None => ctx,
+            },
+        };
+        ret.push(ctx);
+    }
+    Ok(ret)
+}
+
+/// Profile names end up in file names and vim script, so keep them simple.
+pub fn is_valid_profile(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_paths() {
+        let ctx = Context::new("/vim", None);
+        assert_eq!(ctx.packfile, Path::new("/vim/.pack/packfile"));
+        assert_eq!(ctx.plugin_file, Path::new("/vim/plugin/_pack.vim"));
+        assert_eq!(Context::new("/vim", Some(DEFAULT_PROFILE)), ctx);
+
+        let ctx = Context::new("/vim", Some("work")).with_packfile("/dotfiles/packfile");
+        assert_eq!(ctx.packfile, Path::new("/dotfiles/packfile"));
+        assert_eq!(ctx.plugin_file, Path::new("/vim/plugin/_pack_work.vim"));
+        assert_eq!(ctx.profile_name(), "work");
+    }
+
+    #[test]
+    fn other_profiles() {
+        let dir = env::temp_dir().join(format!("pack-profiles-{}", std::process::id()));
+        let ctx = Context::new(&dir, Some("work"));
+        fs::create_dir_all(dir.join(".pack/profiles/home")).unwrap();
+        fs::create_dir_all(dir.join(".pack/profiles/empty")).unwrap();
+        fs::write(dir.join(".pack/packfile"), "").unwrap();
+        fs::write(dir.join(".pack/profiles/home/packfile"), "").unwrap();
+
+        let mut others = ctx.other_profiles().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        others.sort_by(|a, b| a.profile.cmp(&b.profile));
+
+        assert_eq!(
+            others,
+            vec![Context::new(&dir, None), Context::new(&dir, Some("home"))]
+        );
+    }
+
+    #[test]
+    fn neovim_paths() {
+        let ctx = Context::neovim("/config/nvim", "/data/nvim/site", Some("work"));
+        assert_eq!(ctx.pack_dir, Path::new("/data/nvim/site/pack"));
+        assert_eq!(
+            ctx.packfile,
+            Path::new("/config/nvim/.pack/profiles/work/packfile")
+        );
+        assert_eq!(
+            ctx.plugin_file,
+            Path::new("/config/nvim/plugin/_pack_work.lua")
+        );
+        assert_eq!(Target::from_name("nvim"), Some(Target::Neovim));
+    }
+
+    #[test]
+    fn targets_share_config() {
+        let opts = Options {
+            root: Some("/vim"),
+            targets: vec!["vim", "neovim", "vim"],
+            ..Default::default()
+        };
+        let ctxs = contexts_with(&opts, |_| None).unwrap();
+        assert_eq!(ctxs.len(), 2);
+        assert_eq!(ctxs[0].pack_dir, Path::new("/vim/pack"));
+        assert_eq!(ctxs[1].target, Target::Neovim);
+        assert_eq!(ctxs[1].packfile, Path::new("/vim/.pack/packfile"));
+        assert_eq!(ctxs[1].config_dir, Path::new("/vim/.pack"));
+        assert_ne!(ctxs[1].pack_dir, ctxs[0].pack_dir);
+    }
+
+    #[test]
+    fn root_detects_target() {
+        let dir = env::temp_dir().join(format!("pack-root-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let root = dir.to_string_lossy().into_owned();
+        let opts = Options {
+            root: Some(&root),
+            ..Default::default()
+        };
+        let vim = contexts_with(&opts, |_| None).unwrap();
+        fs::write(dir.join("init.lua"), "").unwrap();
+        let neovim = contexts_with(&opts, |_| None).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vim[0].target, Target::Vim);
+        assert_eq!(vim[0].pack_dir, dir.join("pack"));
+        assert_eq!(neovim[0].target, Target::Neovim);
+    }
+
+    #[test]
+    fn profile_names() {
+        assert!(is_valid_profile("work"));
+        assert!(is_valid_profile("no-plugins_2"));
+        assert!(!is_valid_profile(""));
+        assert!(!is_valid_profile("../work"));
+
+        let opts = Options {
+            root: Some("/vim"),
+            ..Default::default()
+        };
+        let env = |name: &str| Some(OsString::from(format!("../{}", name)));
+        assert!(contexts_with(&opts, env).is_err());
+        let opts = Options {
+            profile: Some("work"),
+            ..opts
+        };
+        let ctxs = contexts_with(&opts, |_| Some(OsString::from(""))).unwrap();
+        assert_eq!(ctxs[0].profile_name(), "work");
+    }
+}
@@ -1,61 +1,399 @@
-use lazy_static::lazy_static;
+use crate::package::Package;
+use crate::task::{Event, Outcome, Report, Reporter, Status};
+use crate::utils::json_string;
+
 use std::io::{self, Write};
-use std::sync::Mutex;
-use termion::{clear, color, cursor};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use termion::{clear, color, cursor, is_tty, terminal_size};
+
+const SPINNER_CHARS: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Tasks shown at the bottom of the terminal, redrawn on every change.
+#[derive(Default)]
+struct View {
+    total: usize,
+    finished: usize,
+    /// Packages being synced and what they are doing
+    running: Vec<(String, &'static str)>,
+    failed: Vec<(String, String)>,
+    frame: usize,
+    /// Whether tasks are running and the view is shown
+    active: bool,
+    /// Rows of the view on the screen
+    drawn: u16,
+}
+
+impl View {
+    /// Print `lines` above the view, where they scroll away with the rest of the output,
+    /// and draw the view again. The view is kept below the top row of the terminal, so that
+    /// it can always be moved over and clearing it never clears the whole screen.
+    fn redraw(&mut self, lines: &[String], show: bool) {
+        let (width, height) = terminal_size().unwrap_or((80, 24));
+        let width = (width as usize).saturating_sub(1);
+        let mut out = String::new();
+        if self.drawn > 0 {
+            out.push_str(&format!(
+                "{}\r{}",
+                cursor::Up(self.drawn),
+                clear::AfterCursor
+            ));
+        }
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+
+        let mut rows = Vec::new();
+        if show {
+            rows.push(fit(
+                &format!("Syncing {}/{}", self.finished, self.total),
+                width,
+            ));
+            for (name, error) in &self.failed {
+                rows.push(sign(
+                    '✗',
+                    color::Red,
+                    &fit(&format!("[{}] {}", name, error), width.saturating_sub(3)),
+                ));
+            }
+            let spinner = SPINNER_CHARS[self.frame % SPINNER_CHARS.len()];
+            for (name, state) in &self.running {
+                rows.push(format!(
+                    " {} {}",
+                    spinner,
+                    fit(&format!("[{}] {}", name, state), width.saturating_sub(3))
+                ));
+            }
+            let max = height.saturating_sub(2).max(2) as usize;
+            if rows.len() > max {
+                let more = rows.len() - max + 1;
+                rows.truncate(max - 1);
+                rows.push(format!("   ... {} more", more));
+            }
+        }
+        for row in &rows {
+            out.push_str(row);
+            out.push('\n');
+        }
+        self.drawn = rows.len() as u16;
+
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        let _ = write!(handle, "{}{}{}", cursor::Hide, out, cursor::Show);
+        let _ = handle.flush();
+    }
+}
+
+/// Cut `s` to `width` characters.
+fn fit(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+fn sign<C: color::Color>(c: char, char_color: C, msg: &str) -> String {
+    format!(
+        " {}{}{} {}",
+        color::Fg(char_color),
+        c,
+        color::Fg(color::Reset),
+        msg
+    )
+}
+
+/// Reports task progress on the terminal. Packages being synced and failed ones are shown
+/// below an overall progress line, while finished packages scroll away.
+pub struct Terminal {
+    view: Arc<Mutex<View>>,
+    /// Stops the spinner
+    ticker: Mutex<Option<(Sender<()>, thread::JoinHandle<()>)>>,
+    /// Warnings are printed after the tasks
+    warnings: Mutex<Vec<String>>,
+}
+
+impl Terminal {
+    pub fn new() -> io::Result<Terminal> {
+        let (_, y) = terminal_size()
+            .map_err(|e| io::Error::other(format!("Fail to get terminal size. {}", e)))?;
+        if y <= 2 {
+            return Err(io::Error::other("Terminal size too small."));
+        }
+        Ok(Terminal {
+            view: Arc::new(Mutex::new(View::default())),
+            ticker: Mutex::new(None),
+            warnings: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Update the task of `pack` with `change` and redraw, printing `line` above the view.
+    fn update<F>(&self, pack: &Package, line: Option<String>, change: F)
+    where
+        F: FnOnce(&mut View, usize),
+    {
+        let mut view = self.view.lock().unwrap();
+        let i = view.running.iter().position(|(n, _)| *n == pack.name);
+        if let Some(i) = i {
+            change(&mut view, i);
+        }
+        let lines: Vec<String> = line.into_iter().collect();
+        let active = view.active;
+        view.redraw(&lines, active);
+    }
+}
+
+impl Reporter for Terminal {
+    fn begin(&self, total: usize) {
+        *self.view.lock().unwrap() = View {
+            total,
+            active: true,
+            ..View::default()
+        };
+
+        let (tx, rx) = channel();
+        let view = self.view.clone();
+        let handle = thread::spawn(move || {
+            while rx.recv_timeout(Duration::from_millis(100)).is_err() {
+                let mut view = view.lock().unwrap();
+                view.frame += 1;
+                view.redraw(&[], true);
+            }
+        });
+        *self.ticker.lock().unwrap() = Some((tx, handle));
+    }
+
+    fn event(&self, pack: &Package, event: Event) {
+        match event {
+            Event::Skipped(reason) => {
+                let line = format!("Skip {}: {}", pack.name, reason);
+                self.update(pack, Some(line), |view, i| {
+                    view.running.remove(i);
+                    view.finished += 1;
+                });
+            }
+            Event::Syncing => {
+                let mut view = self.view.lock().unwrap();
+                view.running.push((pack.name.clone(), "syncing"));
+                view.redraw(&[], true);
+            }
+            Event::Building => self.update(pack, None, |view, i| view.running[i].1 = "building"),
+            Event::Done(outcome) => {
+                let msg = format!("[{}] {}", pack.name, outcome.name());
+                let line = sign('✓', color::Green, &msg);
+                self.update(pack, Some(line), |view, i| {
+                    view.running.remove(i);
+                    view.finished += 1;
+                });
+            }
+            Event::Failed { error, .. } => self.update(pack, None, |view, i| {
+                let (name, _) = view.running.remove(i);
+                view.failed.push((name, error.to_string()));
+                view.finished += 1;
+            }),
+            Event::Warning(msg) => {
+                let warning = format!("{}: {}", pack.name, msg);
+                self.warnings.lock().unwrap().push(warning);
+            }
+        }
+    }
+
+    /// Leave the failed tasks on the screen in place of the view.
+    fn end(&self, report: &Report) {
+        if let Some((tx, handle)) = self.ticker.lock().unwrap().take() {
+            let _ = tx.send(());
+            let _ = handle.join();
+        }
+        let mut view = self.view.lock().unwrap();
+        let lines: Vec<String> = view
+            .failed
+            .iter()
+            .map(|(name, error)| sign('✗', color::Red, &format!("[{}] {}", name, error)))
+            .collect();
+        view.active = false;
+        view.redraw(&lines, false);
+        for w in self.warnings.lock().unwrap().iter() {
+            println!("Warning: {}", w);
+        }
+        print_summary(report);
+    }
+}
 
-lazy_static! {
-    static ref MUTEX: Mutex<u16> = Mutex::new(0);
+/// Reports task progress with one plain line per state change of a package, for output
+/// which isn't a terminal.
+#[derive(Default)]
+pub struct Plain {
+    warnings: Mutex<Vec<String>>,
 }
 
-pub fn line() -> u16 {
-    let mut v = MUTEX.lock().unwrap();
-    let current = *v;
-    *v = *v + 1;
+impl Reporter for Plain {
+    fn event(&self, pack: &Package, event: Event) {
+        match event {
+            Event::Skipped(reason) => println!("Skip {}: {}", pack.name, reason),
+            Event::Syncing => println!("[{}] syncing", pack.name),
+            Event::Building => println!("[{}] building", pack.name),
+            Event::Done(outcome) => println!("[{}] {}", pack.name, outcome.name()),
+            Event::Failed { error, .. } => println!("[{}] failed: {}", pack.name, error),
+            Event::Warning(msg) => {
+                let warning = format!("{}: {}", pack.name, msg);
+                self.warnings.lock().unwrap().push(warning);
+            }
+        }
+    }
+
+    fn end(&self, report: &Report) {
+        for w in self.warnings.lock().unwrap().iter() {
+            println!("Warning: {}", w);
+        }
+        print_summary(report);
+    }
+}
+
+/// Print the number of packages and the time spent on them by how their tasks ended. The
+/// errors of failed packages were printed by the reporter already.
+fn print_summary(report: &Report) {
+    let rows = [
+        ("installed", Some(Outcome::Installed)),
+        ("updated", Some(Outcome::Updated)),
+        ("unchanged", Some(Outcome::Unchanged)),
+        ("outdated", Some(Outcome::Outdated)),
+        ("skipped", None),
+        ("failed", None),
+    ];
     println!();
-    current
-}
-
-pub fn async_print(line: u16, right: u16, msg: &str) {
-    let current = MUTEX.lock().unwrap();
-    let offset = *current - line;
-    print!("{}", cursor::Hide);
-    print!(
-        "{}{}{}{}",
-        cursor::Up(offset),
-        msg,
-        cursor::Left(right),
-        cursor::Down(offset)
-    );
-    print!("{}", cursor::Show);
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-    handle.flush().unwrap();
-}
-
-pub fn character<C: color::Color>(line: u16, offset: u16, c: char, char_color: C) {
-    async_print(
-        line,
-        offset + 1,
-        &format!(
-            "{}{}{}{}",
-            cursor::Right(offset),
-            color::Fg(char_color),
-            c,
-            color::Fg(color::Reset)
-        ),
-    );
-}
-
-pub fn inline_message(line: u16, offset: u16, msg: &str) {
-    async_print(
-        line,
-        offset + msg.len() as u16,
-        &format!("{}{}{}", cursor::Right(offset), clear::UntilNewline, msg),
-    );
-}
-
-pub fn message(line: u16, offset: u16, msg: &str) {
-    async_print(line, offset + msg.len() as u16, msg);
+    println!("Finished in {:.1}s", report.duration.as_secs_f64());
+    for (label, outcome) in rows.iter() {
+        let tasks: Vec<_> = report
+            .tasks
+            .iter()
+            .filter(|t| match t.status {
+                Status::Done(o) => Some(o) == *outcome,
+                Status::Skipped(_) => *label == "skipped",
+                Status::Failed { .. } => *label == "failed",
+            })
+            .collect();
+        if tasks.is_empty() {
+            continue;
+        }
+        let duration: f64 = tasks.iter().map(|t| t.duration.as_secs_f64()).sum();
+        println!("  {:<10} {:>4} {:>8.1}s", label, tasks.len(), duration);
+    }
+
+    if report.failed() > 0 {
+        println!("Run `pack retry` to sync the failed packages again.");
+    }
+}
+
+/// Reports task progress as newline-delimited JSON events, ending with a summary.
+#[derive(Default)]
+pub struct Json {
+    warnings: Mutex<usize>,
+}
+
+impl Json {
+    fn print(&self, event: &str, pack: &Package, fields: &[(&str, String)]) {
+        let mut line = format!(
+            "{{\"event\": \"{}\", \"name\": {}",
+            event,
+            json_string(&pack.name)
+        );
+        for (key, value) in fields {
+            line.push_str(&format!(", \"{}\": {}", key, value));
+        }
+        println!("{}}}", line);
+    }
+}
+
+impl Reporter for Json {
+    fn begin(&self, total: usize) {
+        println!("{{\"event\": \"begin\", \"total\": {}}}", total);
+    }
+
+    fn event(&self, pack: &Package, event: Event) {
+        match event {
+            Event::Skipped(reason) => {
+                self.print("skipped", pack, &[("reason", json_string(reason))]);
+            }
+            Event::Syncing => self.print("started", pack, &[]),
+            Event::Building => {
+                self.print("progress", pack, &[("stage", json_string("building"))]);
+            }
+            Event::Done(outcome) => {
+                self.print("done", pack, &[("outcome", json_string(outcome.name()))]);
+            }
+            Event::Failed { error, fatal } => {
+                self.print(
+                    "failed",
+                    pack,
+                    &[
+                        ("kind", json_string(error.kind())),
+                        ("error", json_string(&error.to_string())),
+                        ("fatal", fatal.to_string()),
+                    ],
+                );
+            }
+            Event::Warning(msg) => {
+                *self.warnings.lock().unwrap() += 1;
+                self.print("warning", pack, &[("message", json_string(msg))]);
+            }
+        }
+    }
+
+    /// Print the summary and start counting warnings again, for the run of the next target.
+    fn end(&self, report: &Report) {
+        let mut counts = vec![("total", report.tasks.len()), ("done", report.done())];
+        for outcome in [
+            Outcome::Installed,
+            Outcome::Updated,
+            Outcome::Unchanged,
+            Outcome::Outdated,
+        ] {
+            let n = report
+                .tasks
+                .iter()
+                .filter(|t| matches!(t.status, Status::Done(o) if o == outcome))
+                .count();
+            counts.push((outcome.name(), n));
+        }
+        let skipped = report
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, Status::Skipped(_)))
+            .count();
+        counts.push(("skipped", skipped));
+        counts.push(("failed", report.failed()));
+        counts.push((
+            "warnings",
+            std::mem::take(&mut *self.warnings.lock().unwrap()),
+        ));
+
+        let mut line = String::from("{\"event\": \"summary\"");
+        for (key, n) in counts {
+            line.push_str(&format!(", \"{}\": {}", key, n));
+        }
+        println!(
+            "{}, \"duration\": {:.3}}}",
+            line,
+            report.duration.as_secs_f64()
+        );
+    }
+}
+
+/// How the progress of tasks is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Redraw the terminal, or print plain lines when stdout is not a terminal
+    Terminal,
+    Plain,
+    Json,
+}
+
+/// The reporter printing task progress as `output`.
+pub fn reporter(output: Output) -> io::Result<Arc<dyn Reporter>> {
+    match output {
+        Output::Json => Ok(Arc::new(Json::default())),
+        Output::Terminal if is_tty(&io::stdout()) => Ok(Arc::new(Terminal::new()?)),
+        _ => Ok(Arc::new(Plain::default())),
+    }
 }
 
 #[cfg(test)]
@@ -63,9 +401,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_line() {
-        line();
-        line();
-        assert_eq!(line(), 2);
+    fn fit_width() {
+        assert_eq!(fit("[ä/b] done", 6), "[ä/b] ");
+        assert_eq!(fit("done", 10), "done");
     }
 }
@@ -5,8 +5,6 @@ use std::path::Path;
 use std::path::StripPrefixError;
 use std::result::Result as StdResult;
 
-use git2;
-use walkdir;
 use yaml_rust::{EmitError, ScanError};
 
 pub type Result<T> = StdResult<T, Error>;
@@ -21,8 +19,11 @@ pub enum Error {
     PluginNotInstalled,
     NoPlugin,
     SkipLocal,
+    NoTask,
+    Interrupted,
     PluginInstalled(String),
     PackFile(String),
+    Environment(String),
     CopyDir(String),
     SaveYaml,
     LoadYaml,
@@ -40,6 +41,28 @@ impl Error {
     pub fn plugin_installed<T: AsRef<Path>>(s: T) -> Error {
         Error::PluginInstalled(format!("Plugin already installed under {:?}", s.as_ref()))
     }
+
+    /// Short name of the kind of error, for machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Error::Io(_) => "io",
+            Error::Format => "format",
+            Error::Git(_) => "git",
+            Error::Editor => "editor",
+            Error::Build(_) => "build",
+            Error::PluginNotInstalled => "not_installed",
+            Error::NoPlugin => "no_plugin",
+            Error::SkipLocal => "local",
+            Error::NoTask => "no_task",
+            Error::Interrupted => "interrupted",
+            Error::PluginInstalled(_) => "installed",
+            Error::PackFile(_) => "packfile",
+            Error::Environment(_) => "environment",
+            Error::CopyDir(_) => "copy_dir",
+            Error::SaveYaml => "save_yaml",
+            Error::LoadYaml => "load_yaml",
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -50,19 +73,19 @@ impl From<io::Error> for Error {
 
 impl From<git2::Error> for Error {
     fn from(err: git2::Error) -> Error {
-        Error::Git(err.description().to_string())
+        Error::Git(err.message().to_string())
     }
 }
 
 impl From<walkdir::Error> for Error {
     fn from(err: walkdir::Error) -> Error {
-        Error::copy_dir(err.description())
+        Error::copy_dir(&err.to_string())
     }
 }
 
 impl From<StripPrefixError> for Error {
     fn from(err: StripPrefixError) -> Error {
-        Error::copy_dir(err.description())
+        Error::copy_dir(&err.to_string())
     }
 }
 
@@ -78,9 +101,11 @@ impl From<ScanError> for Error {
     }
 }
 
-impl StdError for Error {
-    fn description(&self) -> &str {
-        match *self {
+impl StdError for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
             Error::Format => "Invalid format",
             Error::SaveYaml => "Fail to save packfile",
             Error::LoadYaml => "Fail to load packfile",
@@ -88,18 +113,16 @@ impl StdError for Error {
             Error::PluginNotInstalled => "Plugin not installed",
             Error::NoPlugin => "Can not find such plugin",
             Error::SkipLocal => "Local plugin. Skipping",
-            Error::Io(ref e) => e.description(),
+            Error::NoTask => "No plugins to sync",
+            Error::Interrupted => "interrupted",
+            Error::Io(ref e) => return write!(f, "{}", e),
             Error::Build(ref s)
             | Error::Git(ref s)
             | Error::CopyDir(ref s)
             | Error::PluginInstalled(ref s)
-            | Error::PackFile(ref s) => s,
-        }
-    }
-}
-
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+            | Error::PackFile(ref s)
+            | Error::Environment(ref s) => s,
+        };
+        write!(f, "{}", msg)
     }
 }
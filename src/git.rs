@@ -11,7 +11,7 @@ fn github_url(name: &str) -> String {
 }
 
 fn fetch(repo: &Repository, name: &str) -> Result<()> {
-    let url = github_url(&name);
+    let url = github_url(name);
 
     let mut opts = git2::FetchOptions::new();
     opts.download_tags(git2::AutotagOption::All)
@@ -23,30 +23,51 @@ fn fetch(repo: &Repository, name: &str) -> Result<()> {
     Ok(())
 }
 
-fn sync_repo(repo: &Repository, name: &str) -> Result<()> {
-    fetch(&repo, name)?;
+/// Returns whether `HEAD` moved.
+fn sync_repo(repo: &Repository, name: &str) -> Result<bool> {
     let reference = "HEAD";
+    let before = repo.refname_to_id(reference)?;
+    fetch(repo, name)?;
     let oid = repo.refname_to_id(reference)?;
     let object = repo.find_object(oid, None)?;
     repo.reset(&object, git2::ResetType::Hard, None)?;
-    update_submodules(&repo)?;
-    Ok(())
+    update_submodules(repo)?;
+    Ok(oid != before)
 }
 
 pub fn clone<P: AsRef<Path>>(name: &str, target: P) -> Result<()> {
     let url = github_url(name);
-    let result = git2::Repository::clone_recurse(&url, &target);
-    if result.is_err() {
-        fs::remove_dir_all(&target)?;
+    if let Err(e) = git2::Repository::clone_recurse(&url, &target) {
+        let _ = fs::remove_dir_all(&target);
+        return Err(e.into());
     }
     Ok(())
 }
 
-pub fn update<P: AsRef<Path>>(name: &str, path: P) -> Result<()> {
+/// Update the repository at `path`. Returns whether it changed.
+pub fn update<P: AsRef<Path>>(name: &str, path: P) -> Result<bool> {
     let repo = Repository::open(&path)?;
     sync_repo(&repo, name)
 }
 
+/// Whether the branch checked out at `path` is behind the one of the repository. Only the
+/// references of the repository are listed, nothing is fetched.
+pub fn is_outdated<P: AsRef<Path>>(name: &str, path: P) -> Result<bool> {
+    let repo = Repository::open(&path)?;
+    let head = repo.head()?;
+    let local = repo.refname_to_id("HEAD")?;
+    let branch = head.name().unwrap_or("HEAD").to_string();
+
+    let mut remote = repo.remote_anonymous(&github_url(name))?;
+    remote.connect(git2::Direction::Fetch)?;
+    let outdated = remote
+        .list()?
+        .iter()
+        .find(|h| h.name() == branch)
+        .is_some_and(|h| h.oid() != local);
+    Ok(outdated)
+}
+
 fn update_submodules(repo: &Repository) -> Result<()> {
     fn add_subrepos(repo: &Repository, list: &mut Vec<Repository>) -> Result<()> {
         for mut subm in repo.submodules()? {
@@ -0,0 +1,232 @@
+//! Help tags of packages, generated the way vim's `:helptags` does.
+
+use crate::utils;
+use crate::Result;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Generate the tags files of the `doc` directory of the package at `path`: `tags` for
+/// `*.txt` help files and `tags-xx` for `*.xxx` translations. Tags files newer than the
+/// help files are kept. Returns a warning for each duplicate tag.
+pub fn generate<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let doc = path.as_ref().join("doc");
+    if !doc.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut langs: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+    for entry in fs::read_dir(&doc)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        if let Some(lang) = help_lang(&name) {
+            langs.entry(lang).or_default().push((name, entry.path()));
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for (lang, mut files) in langs {
+        let tags_file = if lang == "en" {
+            doc.join("tags")
+        } else {
+            doc.join(format!("tags-{}", lang))
+        };
+        if is_up_to_date(&tags_file, &files)? {
+            continue;
+        }
+
+        files.sort();
+        let mut tags = Vec::new();
+        let mut utf8 = false;
+        for (name, path) in &files {
+            let data = fs::read(path)?;
+            utf8 |= !data.is_ascii();
+            // Examples start after a line ending in ` >` and end at a line starting with a
+            // non-white character. They define no tags.
+            let mut in_example = false;
+            for line in data.split(|&b| b == b'\n') {
+                if in_example && line.first().is_none_or(|b| b" \t\r".contains(b)) {
+                    continue;
+                }
+                tags.extend(
+                    parse_line(line)
+                        .into_iter()
+                        .map(|t| (t.to_vec(), name.as_str())),
+                );
+                in_example = line == b">" || line.ends_with(b" >");
+            }
+        }
+        tags.sort();
+
+        for w in tags.windows(2) {
+            if w[0].0 == w[1].0 {
+                warnings.push(format!(
+                    "Duplicate tag \"{}\" in {}",
+                    String::from_utf8_lossy(&w[1].0),
+                    w[1].1
+                ));
+            }
+        }
+
+        // Like vim, mark the tags of non-ASCII help files as UTF-8.
+        let mut out = Vec::new();
+        if utf8 {
+            out.extend_from_slice(b"!_TAG_FILE_ENCODING\tutf-8\t//\n");
+        }
+        for (tag, name) in tags {
+            out.extend_from_slice(&tag);
+            out.push(b'\t');
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(b"\t/*");
+            for &b in &tag {
+                if b == b'\\' || b == b'/' {
+                    out.push(b'\\');
+                }
+                out.push(b);
+            }
+            out.extend_from_slice(b"*\n");
+        }
+        utils::write_atomic(&tags_file, &out)?;
+    }
+    Ok(warnings)
+}
+
+/// Language of a help file: `en` for `*.txt` and `xx` for `*.xxx`.
+fn help_lang(name: &str) -> Option<String> {
+    if name.ends_with(".txt") {
+        return Some(String::from("en"));
+    }
+    let b = name.as_bytes();
+    let n = b.len();
+    if n > 4
+        && b[n - 4] == b'.'
+        && b[n - 3].is_ascii_alphabetic()
+        && b[n - 2].is_ascii_alphabetic()
+        && b[n - 1] == b'x'
+    {
+        Some(name[n - 3..n - 1].to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// A tags file is up to date when it is newer than the help files and only refers to help
+/// files which still exist.
+fn is_up_to_date(tags_file: &Path, files: &[(String, PathBuf)]) -> Result<bool> {
+    let modified = match fs::metadata(tags_file) {
+        Ok(m) => m.modified()?,
+        Err(_) => return Ok(false),
+    };
+    for (_, path) in files {
+        if fs::metadata(path)?.modified()? > modified {
+            return Ok(false);
+        }
+    }
+    let data = fs::read(tags_file)?;
+    Ok(data
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.starts_with(b"!_TAG_"))
+        .all(|line| match line.split(|&b| b == b'\t').nth(1) {
+            Some(name) => files.iter().any(|(f, _)| f.as_bytes() == name),
+            None => line.is_empty(),
+        }))
+}
+
+/// Tags defined on a help file line. A `*tag*` has no white space or `|` in it, and white
+/// space or the line boundaries around it.
+fn parse_line(line: &[u8]) -> Vec<&[u8]> {
+    let mut tags = Vec::new();
+    let mut start = match line.iter().position(|&b| b == b'*') {
+        Some(p) => p,
+        None => return tags,
+    };
+    while let Some(len) = line[start + 1..].iter().position(|&b| b == b'*') {
+        let end = start + 1 + len;
+        let tag = &line[start + 1..end];
+        if !tag.is_empty()
+            && !tag.iter().any(|&b| b == b' ' || b == b'\t' || b == b'|')
+            && (start == 0 || line[start - 1] == b' ' || line[start - 1] == b'\t')
+            && line.get(end + 1).is_none_or(|b| b" \t\r\n".contains(b))
+        {
+            tags.push(tag);
+        }
+        start = end;
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn parse_tags() {
+        let tags = parse_line(b"*fzf.txt*\tfor vim  *fzf* *:FZF* a*b* *c d* *e*f *g|h*");
+        assert_eq!(tags, vec![&b"fzf.txt"[..], b"fzf", b":FZF"]);
+        assert!(parse_line(b"x **").is_empty());
+        assert_eq!(help_lang("fzf.txt"), Some(String::from("en")));
+        assert_eq!(help_lang("fzf.jax"), Some(String::from("ja")));
+        assert_eq!(help_lang("tags"), None);
+    }
+
+    #[test]
+    fn generate_tags() {
+        let dir = env::temp_dir().join(format!("pack-helptags-{}", process::id()));
+        fs::create_dir_all(dir.join("doc")).unwrap();
+        fs::write(
+            dir.join("doc/a.txt"),
+            "*a.txt* *a/b*\n*dup*\nexample: >\n\t*no-tag*\n\n\t*no* *tags*\n*c*\n",
+        )
+        .unwrap();
+        fs::write(dir.join("doc/b.txt"), "*dup*\n").unwrap();
+        fs::write(dir.join("doc/a.jax"), "*a-ja*\n").unwrap();
+
+        let warnings = generate(&dir).unwrap();
+        let tags = fs::read_to_string(dir.join("doc/tags")).unwrap();
+        let ja = fs::read_to_string(dir.join("doc/tags-ja")).unwrap();
+        let again = generate(&dir).unwrap();
+        fs::remove_file(dir.join("doc/b.txt")).unwrap();
+        let removed = generate(&dir).unwrap();
+        let tags_removed = fs::read_to_string(dir.join("doc/tags")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(warnings, vec!["Duplicate tag \"dup\" in b.txt"]);
+        assert_eq!(
+            tags,
+            "a.txt\ta.txt\t/*a.txt*\n\
+             a/b\ta.txt\t/*a\\/b*\n\
+             c\ta.txt\t/*c*\n\
+             dup\ta.txt\t/*dup*\n\
+             dup\tb.txt\t/*dup*\n"
+        );
+        assert_eq!(ja, "a-ja\ta.jax\t/*a-ja*\n");
+        assert!(again.is_empty());
+        assert!(removed.is_empty());
+        assert!(!tags_removed.contains("b.txt"));
+    }
+
+    #[test]
+    fn utf8_encoding_header() {
+        let dir = env::temp_dir().join(format!("pack-helptags-utf8-{}", process::id()));
+        fs::create_dir_all(dir.join("doc")).unwrap();
+        fs::write(dir.join("doc/a.txt"), "*a.txt*\n").unwrap();
+        fs::write(dir.join("doc/a.jax"), "*a-ja*\tヘルプ\n").unwrap();
+
+        generate(&dir).unwrap();
+        let tags = fs::read_to_string(dir.join("doc/tags")).unwrap();
+        let ja = fs::read_to_string(dir.join("doc/tags-ja")).unwrap();
+        let files = [(String::from("a.jax"), dir.join("doc/a.jax"))];
+        let up_to_date = is_up_to_date(&dir.join("doc/tags-ja"), &files).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(tags, "a.txt\ta.txt\t/*a.txt*\n");
+        assert_eq!(ja, "!_TAG_FILE_ENCODING\tutf-8\t//\na-ja\ta.jax\t/*a-ja*\n");
+        assert!(up_to_date);
+    }
+}
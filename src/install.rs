@@ -0,0 +1,293 @@
+use crate::context::Context;
+use crate::git;
+use crate::keymap::KeyMap;
+use crate::package::{self, Package, Packfile};
+use crate::task::{Outcome, Report, Reporter, TaskManager, TaskType};
+use crate::{Error, Result};
+
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Packages to install and how they are set up.
+#[derive(Debug, Clone, Default)]
+pub struct Plugins {
+    pub names: Vec<String>,
+    /// `None` keeps the category of packages already in the packfile
+    pub category: Option<String>,
+    pub opt: bool,
+    pub on: Option<Vec<String>>,
+    pub types: Option<Vec<String>>,
+    pub keys: Option<Vec<KeyMap>>,
+    pub events: Option<Vec<String>>,
+    pub build: Option<String>,
+    pub requires: Option<Vec<String>>,
+    pub threads: usize,
+    pub local: bool,
+}
+
+/// Install `plugins.names`, or every package of the packfile if no names are given, and
+/// save them to the packfile. Packages of the packfile which failed are kept, so that the
+/// next run installs them again, but new ones are only added once installed. Returns the
+/// results of the packages.
+pub fn install_plugins(
+    ctx: &Context,
+    plugins: &Plugins,
+    reporter: Arc<dyn Reporter>,
+) -> Result<Report> {
+    let mut file = package::load(ctx)?;
+    let packs = &mut file.packs;
+    let known = packs
+        .iter()
+        .map(|p| p.name.clone())
+        .collect::<Vec<String>>();
+    let mut queue = Vec::new();
+
+    if plugins.names.is_empty() {
+        queue.extend(packs.iter().cloned());
+    } else {
+        for name in &plugins.names {
+            // Packages of the packfile keep their entry, with the options given on top.
+            // Installed ones are left as they are.
+            let pack = match packs.iter_mut().find(|x| &x.name == name) {
+                Some(x) => {
+                    if !x.is_installed(ctx) {
+                        plugins.apply(x);
+                    }
+                    x.clone()
+                }
+                None => {
+                    let category = plugins.category.as_deref().unwrap_or("default");
+                    let mut p = Package::new(name, category, plugins.opt);
+                    p.local = Path::new(name).is_dir();
+                    plugins.apply(&mut p);
+                    packs.push(p.clone());
+                    p
+                }
+            };
+            queue.push(pack);
+        }
+    }
+    install_queue(ctx, file, queue, &known, plugins.threads, reporter)
+}
+
+impl Plugins {
+    /// Set the options which were given on `pack`.
+    fn apply(&self, pack: &mut Package) {
+        if let Some(ref c) = self.category {
+            pack.set_category(c);
+        }
+        if self.opt {
+            pack.set_opt(true);
+        }
+        if self.local {
+            pack.local = true;
+        }
+        if let Some(ref c) = self.on {
+            pack.set_load_commands(c.clone());
+        }
+        if let Some(ref t) = self.types {
+            pack.set_types(t.clone());
+        }
+        if let Some(ref k) = self.keys {
+            pack.set_keys(k.clone());
+        }
+        if let Some(ref e) = self.events {
+            pack.set_events(e.clone());
+        }
+        if let Some(ref c) = self.build {
+            pack.set_build_command(c);
+        }
+        if let Some(ref r) = self.requires {
+            pack.set_requires(r.clone());
+        }
+    }
+}
+
+/// Install `failed` packages again. Packages of the packfile are installed from their
+/// entry, others, like new packages which failed to install, from the given one.
+pub fn retry_plugins(
+    ctx: &Context,
+    failed: &[Package],
+    threads: usize,
+    reporter: Arc<dyn Reporter>,
+) -> Result<Report> {
+    let mut file = package::load(ctx)?;
+    let known = file
+        .packs
+        .iter()
+        .map(|p| p.name.clone())
+        .collect::<Vec<String>>();
+    let mut queue = Vec::new();
+    for pack in failed {
+        match file.packs.iter().find(|x| x.name == pack.name) {
+            Some(x) => queue.push(x.clone()),
+            None => {
+                file.packs.push(pack.clone());
+                queue.push(pack.clone());
+            }
+        }
+    }
+    install_queue(ctx, file, queue, &known, threads, reporter)
+}
+
+/// Install `queue` with its dependencies and save the packages of `file`, which include
+/// the queued packages, to the packfile. Packages which failed to install and are not among
+/// the `known` packages of the packfile, like a misspelled name, are left out.
+fn install_queue(
+    ctx: &Context,
+    mut file: Packfile,
+    mut queue: Vec<Package>,
+    known: &[String],
+    threads: usize,
+    reporter: Arc<dyn Reporter>,
+) -> Result<Report> {
+    let packs = &mut file.packs;
+    package::move_start_packages(ctx, packs)?;
+    let mut manager = TaskManager::new(ctx, TaskType::Install, threads);
+    add_dependencies(ctx, &mut queue, packs);
+    for pack in package::dependency_order(queue) {
+        manager.add(pack);
+    }
+
+    let report = manager.run(install_plugin, reporter)?;
+
+    let failures = report.failures();
+    packs.retain(|p| {
+        !failures.iter().any(|f| f.name == p.name) || known.contains(&p.name) || p.is_installed(ctx)
+    });
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    package::update_pack_plugin(ctx, packs)?;
+    file.save(ctx)?;
+    Ok(report)
+}
+
+/// Queue the dependencies of queued packages which are not installed yet. Dependencies
+/// missing from the packfile are added with the category and opt of the package requiring them.
+fn add_dependencies(ctx: &Context, queue: &mut Vec<Package>, packs: &mut Vec<Package>) {
+    let mut i = 0;
+    while i < queue.len() {
+        for dep in queue[i].requires.clone() {
+            if queue.iter().any(|x| x.name == dep) {
+                continue;
+            }
+            let pack = match packs.iter().find(|x| x.name == dep) {
+                Some(x) if x.is_installed(ctx) => continue,
+                Some(x) => x.clone(),
+                None => {
+                    let mut p = Package::new(&dep, &queue[i].category, queue[i].opt);
+                    p.local = Path::new(&dep).is_dir();
+                    packs.push(p.clone());
+                    p
+                }
+            };
+            queue.push(pack);
+        }
+        i += 1;
+    }
+}
+
+fn install_plugin(ctx: &Context, pack: &Package) -> (Result<Outcome>, bool) {
+    match do_install(ctx, pack) {
+        Ok(()) => (Ok(Outcome::Installed), true),
+        Err(Error::PluginInstalled(_)) => (Ok(Outcome::Unchanged), true),
+        Err(e) => (Err(e), false),
+    }
+}
+
+fn do_install(ctx: &Context, pack: &Package) -> Result<()> {
+    let path = pack.path(ctx);
+    if path.is_dir() {
+        Err(Error::plugin_installed(&path))
+    } else if pack.local {
+        let src = Path::new(&pack.name);
+        if !src.is_dir() {
+            Err(Error::NoPlugin)
+        } else {
+            symlink(src, &path)?;
+            Ok(())
+        }
+    } else {
+        git::clone(&pack.name, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::echo::Plain;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn install_keeps_packfile_entry() {
+        let dir = env::temp_dir().join(format!("pack-install-{}", process::id()));
+        let src = dir.join("src/my-plugin");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(dir.join("pack/default/opt")).unwrap();
+        let ctx = Context::new(&dir, None);
+
+        let mut pack = Package::new(&src.to_string_lossy(), "default", false);
+        pack.local = true;
+        pack.when.has.push(String::from("python3"));
+        package::save(&ctx, vec![pack.clone()]).unwrap();
+
+        let plugins = Plugins {
+            names: vec![pack.name.clone()],
+            threads: 1,
+            ..Default::default()
+        };
+        let report = install_plugins(&ctx, &plugins, Arc::new(Plain::default())).unwrap();
+        let missing = Plugins {
+            names: vec![dir.join("missing").to_string_lossy().into_owned()],
+            local: true,
+            ..plugins
+        };
+        let failed = install_plugins(&ctx, &missing, Arc::new(Plain::default())).unwrap();
+        let packs = package::fetch(&ctx).unwrap();
+        let installed = pack.path(&ctx).is_dir();
+        let start = dir.join("pack/default/start").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(report.failures().is_empty());
+        assert_eq!(failed.failures()[0].name, missing.names[0]);
+        assert_eq!(packs, vec![pack]);
+        assert!(installed);
+        assert!(!start);
+    }
+
+    #[test]
+    fn retry_failed_new_package() {
+        let dir = env::temp_dir().join(format!("pack-install-retry-{}", process::id()));
+        let src = dir.join("src/late-plugin");
+        fs::create_dir_all(dir.join("pack/lang/opt")).unwrap();
+        let ctx = Context::new(&dir, None);
+
+        let plugins = Plugins {
+            names: vec![src.to_string_lossy().into_owned()],
+            category: Some(String::from("lang")),
+            types: Some(vec![String::from("rust")]),
+            threads: 1,
+            local: true,
+            ..Default::default()
+        };
+        let failed = install_plugins(&ctx, &plugins, Arc::new(Plain::default())).unwrap();
+        let saved = package::fetch(&ctx).unwrap();
+
+        fs::create_dir_all(&src).unwrap();
+        let retried =
+            retry_plugins(&ctx, &failed.failures(), 1, Arc::new(Plain::default())).unwrap();
+        let packs = package::fetch(&ctx).unwrap();
+        let installed = packs.iter().all(|p| p.path(&ctx).is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(saved.is_empty());
+        assert!(retried.failures().is_empty());
+        assert_eq!(packs.len(), 1);
+        assert_eq!(packs[0].category, "lang");
+        assert_eq!(packs[0].for_types, vec!["rust"]);
+        assert!(installed);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Error, Result};
+
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+/// Modes a key mapping can load a package in: normal, visual and select, visual,
+/// operator-pending and insert.
+pub const MODES: &[&str] = &["n", "v", "x", "o", "i"];
+
+/// Key mapping loading a package when it is first used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    /// Keys in `:map` notation, like `ga` or `<Plug>(EasyAlign)`
+    pub lhs: String,
+    pub modes: Vec<String>,
+}
+
+impl KeyMap {
+    /// Normal mode mapping of `lhs`.
+    pub fn new(lhs: &str) -> KeyMap {
+        KeyMap {
+            lhs: lhs.to_string(),
+            modes: vec![String::from("n")],
+        }
+    }
+
+    /// A mapping is either the keys of a normal mode mapping or a hash with the keys
+    /// and one mode or a list of modes.
+    pub fn from_yaml(doc: &Yaml) -> Result<KeyMap> {
+        if let Some(lhs) = doc.as_str() {
+            return Ok(KeyMap::new(lhs));
+        }
+        let lhs = doc["lhs"].as_str().ok_or(Error::Format)?;
+        let modes = match doc["mode"] {
+            Yaml::String(ref m) => vec![m.clone()],
+            Yaml::Array(ref a) => a
+                .iter()
+                .map(|m| m.as_str().map(|s| s.to_string()).ok_or(Error::Format))
+                .collect::<Result<Vec<String>>>()?,
+            Yaml::BadValue => vec![String::from("n")],
+            _ => return Err(Error::Format),
+        };
+        if modes.is_empty() || modes.iter().any(|m| !MODES.contains(&m.as_str())) {
+            return Err(Error::Format);
+        }
+        Ok(KeyMap {
+            lhs: lhs.to_string(),
+            modes,
+        })
+    }
+
+    pub fn into_yaml(self) -> Yaml {
+        if self.modes == ["n"] {
+            return Yaml::String(self.lhs);
+        }
+        let mut doc = Hash::new();
+        doc.insert(Yaml::from_str("lhs"), Yaml::String(self.lhs));
+        let mode = if self.modes.len() == 1 {
+            Yaml::String(self.modes[0].clone())
+        } else {
+            Yaml::Array(self.modes.into_iter().map(Yaml::String).collect())
+        };
+        doc.insert(Yaml::from_str("mode"), mode);
+        Yaml::Hash(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn parse_keymaps() {
+        let docs = YamlLoader::load_from_str(
+            "[ga, {lhs: '<Plug>(EasyAlign)', mode: [n, x]}, {lhs: gs, mode: q}]",
+        )
+        .unwrap();
+        let keys = docs[0].as_vec().unwrap();
+        assert_eq!(KeyMap::from_yaml(&keys[0]).unwrap(), KeyMap::new("ga"));
+        let map = KeyMap::from_yaml(&keys[1]).unwrap();
+        assert_eq!(map.modes, vec!["n", "x"]);
+        assert_eq!(KeyMap::from_yaml(&map.clone().into_yaml()).unwrap(), map);
+        assert!(KeyMap::from_yaml(&keys[2]).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+//! Package manager for vim8.
+//!
+//! Every operation works on the directories given by a `context::Context`.
+//! `package` loads and saves packfiles and generates the plugin file combining package
+//! configurations. `install` and `update` sync packages, reporting their progress to a
+//! `task::Reporter`.
+
+pub mod condition;
+pub mod context;
+pub mod echo;
+mod error;
+pub mod git;
+pub mod helptags;
+pub mod install;
+pub mod keymap;
+pub mod package;
+pub mod retry;
+mod script;
+pub mod task;
+pub mod update;
+pub mod utils;
+
+pub use error::{Error, Result};
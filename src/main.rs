@@ -1,40 +1,63 @@
-use clap::ArgMatches;
+use pack::context;
 use std::env;
 use std::io;
 
-#[macro_use]
-mod utils;
+macro_rules! die {
+    ($($arg:tt)*) => ({
+        use std::io::Write;
+        (writeln!(&mut ::std::io::stderr(), $($arg)*)).expect("stderr");
+        ::std::process::exit(1)
+    })
+}
 
 mod cli;
 mod cmd;
-mod echo;
-mod error;
-mod git;
-mod package;
-mod task;
-
-pub use error::{Error, Result};
 
 fn main() {
-    let _ = env::var("PACK_LOG_FILE").and_then(|x| {
+    if let Ok(x) = env::var("PACK_LOG_FILE") {
         simple_logging::log_to_file(x, log::LevelFilter::Info).expect("fail to init logging");
-        Ok(())
-    });
+    }
 
     let app_m = cli::build_cli().get_matches();
 
+    let profile = app_m
+        .subcommand()
+        .1
+        .and_then(|m| m.value_of("profile"))
+        .or_else(|| app_m.value_of("profile"))
+        .map(String::from)
+        .or_else(|| env::var("PACK_PROFILE").ok());
+    let opts = context::Options {
+        root: app_m.value_of("root"),
+        profile: profile.as_deref(),
+        packfile: app_m.value_of("packfile"),
+        targets: app_m
+            .values_of("target")
+            .map(|v| v.collect())
+            .unwrap_or_default(),
+    };
+    let ctxs = match context::contexts(&opts) {
+        Ok(c) => c,
+        Err(e) => die!("Err: {}", e),
+    };
+
     match app_m.subcommand() {
-        ("list", Some(m)) => cmd::list::exec(m),
-        ("install", Some(m)) => cmd::install::exec(m),
-        ("uninstall", Some(m)) => cmd::uninstall::exec(m),
-        ("config", Some(m)) => cmd::config::exec(m),
-        ("move", Some(m)) => cmd::move_cmd::exec(m),
-        ("update", Some(m)) => cmd::update::exec(m),
-        ("generate", Some(m)) => cmd::generate::exec(m),
+        ("list", Some(m)) => cmd::list::exec(&ctxs, m),
+        ("install", Some(m)) => cmd::install::exec(&ctxs, m),
+        ("uninstall", Some(m)) => cmd::uninstall::exec(&ctxs, m),
+        ("config", Some(m)) => cmd::config::exec(&ctxs, m),
+        ("move", Some(m)) => cmd::move_cmd::exec(&ctxs, m),
+        ("update", Some(m)) => cmd::update::exec(&ctxs, m),
+        ("outdated", Some(m)) => cmd::outdated::exec(&ctxs, m),
+        ("retry", Some(m)) => cmd::retry::exec(&ctxs, m),
+        ("generate", Some(m)) => cmd::generate::exec(&ctxs, m),
+        ("import", Some(m)) => cmd::import::exec(&ctxs, m),
+        ("export", Some(m)) => cmd::export::exec(&ctxs, m),
         ("completions", Some(m)) => {
             let shell = m.value_of("SHELL").unwrap();
             cli::build_cli().gen_completions_to("pack", shell.parse().unwrap(), &mut io::stdout());
         }
-        _ => cmd::list::exec(&ArgMatches::default()),
+        // Without a subcommand only the global options, like --output, are given.
+        _ => cmd::list::exec(&ctxs, &app_m),
     }
 }
@@ -1,58 +1,52 @@
+use crate::condition::Condition;
+use crate::context::Context;
+use crate::git;
+use crate::keymap::KeyMap;
+use crate::script::Syntax;
+use crate::utils;
 use crate::{Error, Result};
 
-use std::env;
 use std::fmt;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 
-use lazy_static::lazy_static;
+use walkdir::WalkDir;
 use yaml_rust::yaml::Hash;
 use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
-const PACK_PLUGIN_FILE: &str = "_pack.vim";
-const PLUGIN_HEADER: &str = "\" Generated by pack. DO NOT EDIT!
-
-scriptencoding utf-8
-
-function! s:do_cmd(cmd, bang, start, end, args)
-    exec printf('%s%s%s %s', (a:start == a:end ? '' : (a:start.','.a:end)), a:cmd, a:bang, a:args)
-endfunction
-";
-
 const PACKFILE_HEADER: &[u8] = b"# vim: ft=yaml
 #
 # Generated by pack.
 
 ";
 
-lazy_static! {
-    static ref BASE_DIR: PathBuf = env::var("VIM_CONFIG_PATH")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let home = dirs::home_dir().expect("No home directory found");
-            home.join(".vim")
-        });
-    static ref PACK_DIR: PathBuf = (*BASE_DIR).join("pack");
-    static ref PACK_CONFIG_DIR: PathBuf = (*BASE_DIR).join(".pack");
-    static ref PACK_FILE: PathBuf = (*PACK_CONFIG_DIR).join("packfile");
-    static ref PACK_PLUGIN_DIR: PathBuf = (*BASE_DIR).join("plugin");
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Package {
     pub name: String,
     pub category: String,
     pub opt: bool,
-    /// Load this package on this command
-    pub load_command: Option<String>,
+    /// Load this package on these commands
+    pub load_commands: Vec<String>,
     /// Load this package for these types
     pub for_types: Vec<String>,
+    /// Load this package on these key mappings
+    pub keys: Vec<KeyMap>,
+    /// Load this package on these autocmd events, each optionally followed by a pattern
+    pub events: Vec<String>,
+    /// Load this package when a function matching one of these autoload prefixes is called
+    pub funcs: Vec<String>,
+    /// Load this package when one of these Lua modules is required, neovim only
+    pub modules: Vec<String>,
     /// Build command for this package
     pub build_command: Option<String>,
     /// Local plugin
     pub local: bool,
+    /// Packages this package depends on
+    pub requires: Vec<String>,
+    /// Use this package only under these conditions
+    pub when: Condition,
 }
 
 impl Package {
@@ -61,15 +55,21 @@ impl Package {
             name: name.to_string(),
             category: category.to_string(),
             opt,
-            load_command: None,
+            load_commands: Vec::new(),
             for_types: Vec::new(),
+            keys: Vec::new(),
+            events: Vec::new(),
+            funcs: Vec::new(),
+            modules: Vec::new(),
             build_command: None,
             local: false,
+            requires: Vec::new(),
+            when: Condition::default(),
         }
     }
 
-    pub fn is_installed(&self) -> bool {
-        self.path().is_dir()
+    pub fn is_installed(&self, ctx: &Context) -> bool {
+        self.path(ctx).is_dir()
     }
 
     pub fn set_category<T: Into<String>>(&mut self, cat: T) {
@@ -80,18 +80,38 @@ impl Package {
         self.opt = opt;
     }
 
-    pub fn set_load_command(&mut self, cmd: &str) {
-        self.load_command = Some(cmd.to_string())
+    pub fn set_load_commands(&mut self, cmds: Vec<String>) {
+        self.load_commands = cmds
     }
 
     pub fn set_types(&mut self, types: Vec<String>) {
         self.for_types = types
     }
 
+    pub fn set_keys(&mut self, keys: Vec<KeyMap>) {
+        self.keys = keys
+    }
+
+    pub fn set_events(&mut self, events: Vec<String>) {
+        self.events = events
+    }
+
+    pub fn set_funcs(&mut self, funcs: Vec<String>) {
+        self.funcs = funcs
+    }
+
+    pub fn set_modules(&mut self, modules: Vec<String>) {
+        self.modules = modules
+    }
+
     pub fn set_build_command(&mut self, cmd: &str) {
         self.build_command = Some(cmd.to_string())
     }
 
+    pub fn set_requires(&mut self, requires: Vec<String>) {
+        self.requires = requires
+    }
+
     pub fn from_yaml(doc: &Yaml) -> Result<Package> {
         let name = doc["name"]
             .as_str()
@@ -102,29 +122,35 @@ impl Package {
             .as_str()
             .map(|s| s.to_string())
             .ok_or(Error::Format)?;
-        let cmd = doc["on"].as_str().map(|s| s.to_string());
         let build = doc["build"].as_str().map(|s| s.to_string());
         let is_local = doc["local"].as_bool().unwrap_or(false);
 
-        let types = match doc["for"].as_vec() {
-            Some(f) => {
-                let mut types = Vec::with_capacity(f.len());
-                for e in f {
-                    types.push(e.as_str().map(|s| s.to_string()).ok_or(Error::Format)?);
-                }
-                types
-            }
+        let cmds = utils::string_list(&doc["on"])?;
+        let types = utils::string_list(&doc["for"])?;
+        let keys = match doc["keys"].as_vec() {
+            Some(k) => k.iter().map(KeyMap::from_yaml).collect::<Result<_>>()?,
             None => vec![],
         };
+        let events = utils::string_list(&doc["event"])?;
+        let funcs = utils::string_list(&doc["on_func"])?;
+        let modules = utils::string_list(&doc["on_module"])?;
+        let requires = utils::string_list(&doc["requires"])?;
+        let when = Condition::from_yaml(&doc["when"])?;
 
         Ok(Package {
             name,
             category,
             opt,
-            load_command: cmd,
+            load_commands: cmds,
             for_types: types,
+            keys,
+            events,
+            funcs,
+            modules,
             build_command: build,
             local: is_local,
+            requires,
+            when,
         })
     }
 
@@ -134,8 +160,21 @@ impl Package {
         doc.insert(Yaml::from_str("category"), Yaml::from_str(&self.category));
         doc.insert(Yaml::from_str("opt"), Yaml::Boolean(self.opt));
         doc.insert(Yaml::from_str("local"), Yaml::Boolean(self.local));
-        if let Some(ref c) = self.load_command {
-            doc.insert(Yaml::from_str("on"), Yaml::from_str(c));
+        // A single command is kept a string, as packfiles written before lists were
+        // accepted have it.
+        match self.load_commands.len() {
+            0 => {}
+            1 => {
+                doc.insert(Yaml::from_str("on"), Yaml::from_str(&self.load_commands[0]));
+            }
+            _ => {
+                let cmds = self
+                    .load_commands
+                    .iter()
+                    .map(|e| Yaml::from_str(e))
+                    .collect::<Vec<Yaml>>();
+                doc.insert(Yaml::from_str("on"), Yaml::Array(cmds));
+            }
         }
         if let Some(ref c) = self.build_command {
             doc.insert(Yaml::from_str("build"), Yaml::from_str(c));
@@ -148,6 +187,45 @@ impl Package {
                 .collect::<Vec<Yaml>>();
             doc.insert(Yaml::from_str("for"), Yaml::Array(types));
         }
+        if !self.keys.is_empty() {
+            let keys = self.keys.into_iter().map(|k| k.into_yaml()).collect();
+            doc.insert(Yaml::from_str("keys"), Yaml::Array(keys));
+        }
+        if !self.events.is_empty() {
+            let events = self
+                .events
+                .iter()
+                .map(|e| Yaml::from_str(e))
+                .collect::<Vec<Yaml>>();
+            doc.insert(Yaml::from_str("event"), Yaml::Array(events));
+        }
+        if !self.funcs.is_empty() {
+            let funcs = self
+                .funcs
+                .iter()
+                .map(|e| Yaml::from_str(e))
+                .collect::<Vec<Yaml>>();
+            doc.insert(Yaml::from_str("on_func"), Yaml::Array(funcs));
+        }
+        if !self.modules.is_empty() {
+            let modules = self
+                .modules
+                .iter()
+                .map(|e| Yaml::from_str(e))
+                .collect::<Vec<Yaml>>();
+            doc.insert(Yaml::from_str("on_module"), Yaml::Array(modules));
+        }
+        if !self.requires.is_empty() {
+            let requires = self
+                .requires
+                .iter()
+                .map(|e| Yaml::from_str(e))
+                .collect::<Vec<Yaml>>();
+            doc.insert(Yaml::from_str("requires"), Yaml::Array(requires));
+        }
+        if !self.when.is_empty() {
+            doc.insert(Yaml::from_str("when"), self.when.into_yaml());
+        }
         Yaml::Hash(doc)
     }
 
@@ -161,47 +239,90 @@ impl Package {
             .unwrap_or("")
     }
 
-    pub fn path(&self) -> PathBuf {
-        let repo = if self.local {
+    /// Name of the package directory, which is also the name `packadd` takes.
+    pub fn dir_name(&self) -> &str {
+        if self.local {
             self.basename()
         } else {
-            let (_, repo) = self.repo();
-            repo
-        };
-        if self.opt {
-            PACK_DIR.join(&self.category).join("opt").join(repo)
-        } else {
-            PACK_DIR.join(&self.category).join("start").join(repo)
+            self.repo().1
         }
     }
 
-    pub fn config_path(&self) -> PathBuf {
+    /// Start packages are installed as opt packages too and loaded by the generated plugin
+    /// file, so that they are only active for their profile and when their conditions are met.
+    pub fn path(&self, ctx: &Context) -> PathBuf {
+        ctx.pack_dir
+            .join(&self.category)
+            .join("opt")
+            .join(self.dir_name())
+    }
+
+    /// Config run at startup, before the package is loaded.
+    pub fn config_path(&self, ctx: &Context) -> PathBuf {
+        self.config_file(ctx, "")
+    }
+
+    /// Config run right after the package is loaded.
+    pub fn after_config_path(&self, ctx: &Context) -> PathBuf {
+        self.config_file(ctx, ".after")
+    }
+
+    /// Vim script and Lua files of the config run before the package is loaded, or right
+    /// after it is loaded if `after` is set.
+    pub fn config_files(&self, ctx: &Context, after: bool) -> [PathBuf; 2] {
+        let vim = if after {
+            self.after_config_path(ctx)
+        } else {
+            self.config_path(ctx)
+        };
+        let lua = vim.with_extension("lua");
+        [vim, lua]
+    }
+
+    fn config_file(&self, ctx: &Context, section: &str) -> PathBuf {
         let name = if self.local {
             self.basename().to_string()
         } else {
             self.name.replace("/", "-")
         };
-        let fname = if name.ends_with(".vim") {
-            name
-        } else {
-            format!("{}.vim", &name)
-        };
-        PACK_CONFIG_DIR.join(fname)
+        let name = name.strip_suffix(".vim").unwrap_or(&name);
+        ctx.config_dir.join(format!("{}{}.vim", name, section))
     }
 
     pub fn repo(&self) -> (&str, &str) {
         let mut info: Vec<&str> = self.name.splitn(2, '/').collect();
         info.reverse();
 
-        let repo = info.iter().next().unwrap_or(&"");
-        let user = info.iter().next().unwrap_or(&"");
+        let repo = info.first().unwrap_or(&"");
+        let user = info.get(1).unwrap_or(&"");
 
         (user, repo)
     }
 
-    pub fn try_build(&self) -> Result<()> {
+    /// Autocmd events and patterns loading the package for its `event` and `on_func`
+    /// triggers. Events without a pattern match every file, and a function prefix ending
+    /// in `#` matches the functions of that autoload script.
+    pub fn trigger_events(&self) -> Vec<(&str, String)> {
+        let events = self
+            .events
+            .iter()
+            .map(|e| match e.trim().split_once(char::is_whitespace) {
+                Some((event, pattern)) => (event, pattern.trim().to_string()),
+                None => (e.trim(), String::from("*")),
+            });
+        let funcs = self.funcs.iter().map(|f| {
+            if f.ends_with('#') {
+                ("FuncUndefined", format!("{}*", f))
+            } else {
+                ("FuncUndefined", f.clone())
+            }
+        });
+        events.chain(funcs).collect()
+    }
+
+    pub fn try_build(&self, ctx: &Context) -> Result<()> {
         if let Some(ref c) = self.build_command {
-            let path = self.path();
+            let path = self.path(ctx);
             let p = process::Command::new("sh")
                 .arg("-c")
                 .arg(c)
@@ -223,9 +344,10 @@ impl Package {
 impl fmt::Display for Package {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let name = if self.opt { "opt" } else { "start" };
-        let on = match self.load_command {
-            Some(ref c) => format!(" [Load on `{}`]", c),
-            None => "".to_string(),
+        let on = if !self.load_commands.is_empty() {
+            format!(" [Load on `{}`]", self.load_commands.join("`,`"))
+        } else {
+            "".to_string()
         };
 
         let types = if !self.for_types.is_empty() {
@@ -234,24 +356,228 @@ impl fmt::Display for Package {
         } else {
             "".to_string()
         };
+        let keys = if !self.keys.is_empty() {
+            let keys = self
+                .keys
+                .iter()
+                .map(|k| k.lhs.as_str())
+                .collect::<Vec<&str>>();
+            format!(" [Keys {}]", keys.join(","))
+        } else {
+            "".to_string()
+        };
+        let events = if !self.events.is_empty() {
+            format!(" [Events {}]", self.events.join(","))
+        } else {
+            "".to_string()
+        };
+        let funcs = if !self.funcs.is_empty() {
+            format!(" [Funcs {}]", self.funcs.join(","))
+        } else {
+            "".to_string()
+        };
+        let modules = if !self.modules.is_empty() {
+            format!(" [Modules {}]", self.modules.join(","))
+        } else {
+            "".to_string()
+        };
+        let requires = if !self.requires.is_empty() {
+            format!(" [Requires {}]", self.requires.join(","))
+        } else {
+            "".to_string()
+        };
         write!(
             f,
-            "{} => pack/{}/{}{}{}",
-            &self.name, &self.category, name, on, types
+            "{} => pack/{}/{}{}{}{}{}{}{}{}",
+            &self.name, &self.category, name, on, types, keys, events, funcs, modules, requires
         )
     }
 }
 
-pub fn fetch() -> Result<Vec<Package>> {
-    if PACK_FILE.is_file() {
-        fetch_from_packfile(&*PACK_FILE)
-            .map_err(|e| Error::PackFile(format!("Fail to parse packfile: {}", e)))
-    } else {
-        Ok(vec![])
+/// Another packfile merged into the packfile, either a local file or a file in a
+/// github repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Include {
+    pub path: String,
+    pub repo: Option<String>,
+}
+
+impl Include {
+    fn from_yaml(doc: &Yaml) -> Result<Include> {
+        if let Some(path) = doc.as_str() {
+            return Ok(Include {
+                path: path.to_string(),
+                repo: None,
+            });
+        }
+        let path = doc["path"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or(Error::Format)?;
+        let repo = doc["repo"].as_str().map(|s| s.to_string());
+        Ok(Include { path, repo })
+    }
+
+    fn into_yaml(self) -> Yaml {
+        let value = match self.repo {
+            None => Yaml::String(self.path),
+            Some(repo) => {
+                let mut doc = Hash::new();
+                doc.insert(Yaml::from_str("repo"), Yaml::String(repo));
+                doc.insert(Yaml::from_str("path"), Yaml::String(self.path));
+                Yaml::Hash(doc)
+            }
+        };
+        let mut doc = Hash::new();
+        doc.insert(Yaml::from_str("include"), value);
+        Yaml::Hash(doc)
+    }
+
+    /// Directory a repository include is cloned to.
+    fn repo_dir(ctx: &Context, repo: &str) -> PathBuf {
+        ctx.config_dir.join("includes").join(repo.replace("/", "-"))
+    }
+
+    /// Location of the included packfile. Local paths are relative to the directory of the
+    /// including packfile. Repositories are cloned by `sync_includes`.
+    fn resolve(&self, ctx: &Context, base: &Path) -> Result<PathBuf> {
+        match self.repo {
+            Some(ref repo) => {
+                let dir = Include::repo_dir(ctx, repo);
+                if !dir.is_dir() {
+                    return Err(Error::PackFile(format!(
+                        "Included repository {} is not cloned, run `pack install` first",
+                        repo
+                    )));
+                }
+                Ok(dir.join(&self.path))
+            }
+            None => match self.path.strip_prefix("~/") {
+                Some(rest) => Ok(dirs::home_dir()
+                    .ok_or_else(|| Error::PackFile(String::from("No home directory found")))?
+                    .join(rest)),
+                None => Ok(base.join(&self.path)),
+            },
+        }
     }
 }
 
-fn fetch_from_packfile<P: AsRef<Path>>(packfile: P) -> Result<Vec<Package>> {
+/// An item of a packfile.
+#[derive(Debug)]
+enum Entry {
+    Include(Include),
+    Package(Box<Package>),
+    /// Remove a package added by an earlier layer.
+    Disable(String),
+}
+
+impl Entry {
+    fn from_yaml(doc: &Yaml) -> Result<Entry> {
+        if !doc["include"].is_badvalue() {
+            Ok(Entry::Include(Include::from_yaml(&doc["include"])?))
+        } else if doc["disable"].as_bool() == Some(true) {
+            let name = doc["name"].as_str().ok_or(Error::Format)?;
+            Ok(Entry::Disable(name.to_string()))
+        } else {
+            Ok(Entry::Package(Box::new(Package::from_yaml(doc)?)))
+        }
+    }
+
+    fn into_yaml(self) -> Yaml {
+        match self {
+            Entry::Include(i) => i.into_yaml(),
+            Entry::Package(p) => p.into_yaml(),
+            Entry::Disable(name) => {
+                let mut doc = Hash::new();
+                doc.insert(Yaml::from_str("name"), Yaml::String(name));
+                doc.insert(Yaml::from_str("disable"), Yaml::Boolean(true));
+                Yaml::Hash(doc)
+            }
+        }
+    }
+}
+
+/// Add `pack` or replace the package with the same name added by an earlier layer.
+fn merge(packs: &mut Vec<Package>, pack: Package) {
+    match packs.iter_mut().find(|x| x.name == pack.name) {
+        Some(x) => *x = pack,
+        None => packs.push(pack),
+    }
+}
+
+/// Packages of a packfile merged with the packfiles it includes, read once to be changed
+/// and saved back.
+#[derive(Debug)]
+pub struct Packfile {
+    pub packs: Vec<Package>,
+    includes: Vec<Entry>,
+    /// Packages provided by the includes alone
+    base: Vec<Package>,
+}
+
+impl Packfile {
+    /// Write `packs` to the packfile of `ctx`, keeping the includes and only the changes
+    /// made on top of them.
+    pub fn save(self, ctx: &Context) -> Result<()> {
+        let entries = overlay(self.includes, &self.base, self.packs)
+            .into_iter()
+            .map(|e| e.into_yaml())
+            .collect::<Vec<Yaml>>();
+        let doc = Yaml::Array(entries);
+        let mut out = String::new();
+        {
+            let mut emitter = YamlEmitter::new(&mut out);
+            emitter.dump(&doc)?;
+        }
+        if let Some(dir) = ctx.packfile.parent() {
+            if !dir.is_dir() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        let mut data = PACKFILE_HEADER.to_vec();
+        data.extend_from_slice(out.as_bytes());
+        utils::write_atomic(&ctx.packfile, &data)
+    }
+}
+
+/// Read the packfile of `ctx` with its includes. Repositories of remote includes must be
+/// cloned already.
+pub fn load(ctx: &Context) -> Result<Packfile> {
+    let mut file = Packfile {
+        packs: Vec::new(),
+        includes: Vec::new(),
+        base: Vec::new(),
+    };
+    if ctx.packfile.is_file() {
+        load_layers(ctx, &mut file)
+            .map_err(|e| Error::PackFile(format!("Fail to parse packfile: {}", e)))?;
+    }
+    Ok(file)
+}
+
+fn load_layers(ctx: &Context, file: &mut Packfile) -> Result<()> {
+    let mut visited = vec![ctx.packfile.canonicalize()?];
+    let dir = ctx.packfile.parent().unwrap_or_else(|| Path::new(""));
+    for entry in fetch_from_packfile(&ctx.packfile)? {
+        match entry {
+            Entry::Include(ref i) => {
+                let path = i.resolve(ctx, dir)?;
+                fetch_layers(ctx, &path, &mut file.packs, &mut visited)?;
+                fetch_layers(ctx, &path, &mut file.base, &mut visited)?;
+                file.includes.push(entry);
+            }
+            Entry::Package(p) => merge(&mut file.packs, *p),
+            Entry::Disable(name) => file.packs.retain(|x| x.name != name),
+        }
+    }
+    Ok(())
+}
+
+pub fn fetch(ctx: &Context) -> Result<Vec<Package>> {
+    Ok(load(ctx)?.packs)
+}
+
+fn fetch_from_packfile<P: AsRef<Path>>(packfile: P) -> Result<Vec<Entry>> {
     let mut data = String::new();
     File::open(packfile.as_ref())?.read_to_string(&mut data)?;
     let docs = YamlLoader::load_from_str(&data)?;
@@ -260,82 +586,398 @@ fn fetch_from_packfile<P: AsRef<Path>>(packfile: P) -> Result<Vec<Package>> {
     if !docs.is_empty() {
         if let Some(doc) = docs[0].as_vec() {
             for d in doc {
-                ret.push(Package::from_yaml(d)?);
+                ret.push(Entry::from_yaml(d)?);
             }
         }
     }
     Ok(ret)
 }
 
-pub fn save(packs: Vec<Package>) -> Result<()> {
-    let packs = packs
-        .into_iter()
-        .map(|e| e.into_yaml())
-        .collect::<Vec<Yaml>>();
-    let doc = Yaml::Array(packs);
-    let mut out = String::new();
-    {
-        let mut emitter = YamlEmitter::new(&mut out);
-        emitter.dump(&doc)?;
-    }
-    if !PACK_CONFIG_DIR.is_dir() {
-        fs::create_dir_all(&*PACK_CONFIG_DIR)?;
-    }
-    let mut f = File::create(&*PACK_FILE)?;
-    f.write_all(PACKFILE_HEADER)?;
-    f.write_all(out.as_bytes())?;
+/// Merge the packages of `packfile` and the packfiles it includes, in order, into `packs`.
+fn fetch_layers(
+    ctx: &Context,
+    packfile: &Path,
+    packs: &mut Vec<Package>,
+    visited: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let canonical = packfile.canonicalize()?;
+    if visited.contains(&canonical) {
+        return Err(Error::PackFile(format!(
+            "Packfile included recursively: {}",
+            packfile.display()
+        )));
+    }
+    visited.push(canonical);
+
+    let base = packfile.parent().unwrap_or_else(|| Path::new(""));
+    for entry in fetch_from_packfile(packfile)? {
+        match entry {
+            Entry::Include(i) => fetch_layers(ctx, &i.resolve(ctx, base)?, packs, visited)?,
+            Entry::Package(p) => merge(packs, *p),
+            Entry::Disable(name) => packs.retain(|x| x.name != name),
+        }
+    }
+
+    visited.pop();
+    Ok(())
+}
+
+/// Clone the repositories of remote includes which are missing, and with `pull` fetch
+/// the latest version of the others, so that reading the packfile never touches the network.
+pub fn sync_includes(ctx: &Context, pull: bool) -> Result<()> {
+    let mut visited = Vec::new();
+    if ctx.packfile.is_file() {
+        sync_layers(ctx, &ctx.packfile, pull, &mut visited)?;
+    }
+    Ok(())
+}
+
+fn sync_layers(
+    ctx: &Context,
+    packfile: &Path,
+    pull: bool,
+    visited: &mut Vec<PathBuf>,
+) -> Result<()> {
+    // Recursive includes are reported when the packfile is read.
+    let canonical = packfile.canonicalize()?;
+    if visited.contains(&canonical) {
+        return Ok(());
+    }
+    visited.push(canonical);
+
+    let base = packfile.parent().unwrap_or_else(|| Path::new(""));
+    for entry in fetch_from_packfile(packfile)? {
+        if let Entry::Include(i) = entry {
+            if let Some(ref repo) = i.repo {
+                let dir = Include::repo_dir(ctx, repo);
+                if !dir.is_dir() {
+                    git::clone(repo, &dir)?;
+                } else if pull {
+                    git::update(repo, &dir)?;
+                }
+            }
+            let path = i.resolve(ctx, base)?;
+            if path.is_file() {
+                sync_layers(ctx, &path, pull, visited)?;
+            }
+        }
+    }
     Ok(())
 }
 
-pub fn update_pack_plugin(packs: &[Package]) -> Result<()> {
-    if !PACK_PLUGIN_DIR.is_dir() {
-        fs::create_dir_all(&*PACK_PLUGIN_DIR)?;
+/// Compute the entries to write so that merging the includes with them gives `packs`.
+fn overlay(includes: Vec<Entry>, base: &[Package], packs: Vec<Package>) -> Vec<Entry> {
+    let mut entries = includes;
+    for p in base {
+        if !packs.iter().any(|x| x.name == p.name) {
+            entries.push(Entry::Disable(p.name.clone()));
+        }
     }
+    for p in packs {
+        if !base.contains(&p) {
+            entries.push(Entry::Package(Box::new(p)));
+        }
+    }
+    entries
+}
+
+/// Write `packs` to the packfile of `ctx`, see `Packfile::save`.
+pub fn save(ctx: &Context, packs: Vec<Package>) -> Result<()> {
+    let mut file = load(ctx)?;
+    file.packs = packs;
+    file.save(ctx)
+}
+
+/// Regenerate the plugin file from the packfile.
+pub fn generate(ctx: &Context) -> Result<()> {
+    let mut packs = fetch(ctx)?;
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    move_start_packages(ctx, &packs)?;
+    update_pack_plugin(ctx, &packs)
+}
+
+/// Move start packages installed by earlier versions, which vim loads in every profile,
+/// from the `start` directory to their path.
+pub fn move_start_packages(ctx: &Context, packs: &[Package]) -> Result<()> {
+    for p in packs.iter().filter(|p| !p.opt) {
+        let old = ctx
+            .pack_dir
+            .join(&p.category)
+            .join("start")
+            .join(p.dir_name());
+        let path = p.path(ctx);
+        if fs::symlink_metadata(&old).is_err() || fs::symlink_metadata(&path).is_ok() {
+            continue;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::rename(&old, &path)?;
+    }
+    Ok(())
+}
+
+pub fn update_pack_plugin(ctx: &Context, packs: &[Package]) -> Result<()> {
+    if !ctx.plugin_dir.is_dir() {
+        fs::create_dir_all(&ctx.plugin_dir)?;
+    }
+
+    let syntax = Syntax::for_target(ctx.target);
+    let mut f = Vec::new();
+    f.write_all(syntax.header(ctx.profile_name()).as_bytes())?;
 
-    let mut f = File::create(PACK_PLUGIN_DIR.join(PACK_PLUGIN_FILE))?;
-    f.write_all(format!("{}\n\n", PLUGIN_HEADER).as_bytes())?;
+    // Packages whose system conditions fail or which are for other editors are not installed.
+    let packs = packs
+        .iter()
+        .filter(|p| p.when.matches_system() && p.when.matches_target(ctx.target))
+        .cloned()
+        .collect::<Vec<Package>>();
 
-    let mut buf = String::new();
-    for (p, path) in packs.iter().map(|x| (x, x.config_path())) {
-        buf.clear();
-        let mut written = false;
+    for p in &packs {
+        let mut block = String::new();
 
-        if let Some(ref c) = p.load_command {
-            f.write_all(format!("\" {}\n", &p.name).as_bytes())?;
-            written = true;
-            let (_, repo) = p.repo();
-            let msg = format!(
-                "command! -nargs=* -range -bang {cmd} packadd {repo} | \
-                 call s:do_cmd('{cmd}', \"<bang>\", <line1>, <line2>, <q-args>)\n\n",
-                cmd = c,
-                repo = repo
-            );
-            f.write_all(msg.as_bytes())?;
+        if !p.load_commands.is_empty() {
+            let names = load_order(p, &packs);
+            for c in &p.load_commands {
+                block.push_str(&syntax.command_stub(&group_name(p), c, &names));
+            }
+            block.push('\n');
         }
 
-        if !p.for_types.is_empty() {
-            if !written {
-                f.write_all(format!("\" {}\n", &p.name).as_bytes())?;
-                written = true;
+        // The filetypes of a package loaded for them are only known to vim after its
+        // ftdetect scripts ran.
+        if p.opt && !p.for_types.is_empty() {
+            if let Ok(dir) = p.path(ctx).strip_prefix(&ctx.pack_dir) {
+                let dir = Path::new("pack").join(dir);
+                block.push_str(&syntax.ftdetect(&dir.to_string_lossy()));
+                block.push('\n');
             }
-            let (_, repo) = p.repo();
-            let msg = format!(
-                "autocmd FileType {} packadd {}\n\n",
-                p.for_types.join(","),
-                repo
-            );
-            f.write_all(msg.as_bytes())?;
-        }
-
-        if path.is_file() {
-            File::open(&path)?.read_to_string(&mut buf)?;
-            if !written {
-                f.write_all(format!("\" {}\n", &p.name).as_bytes())?;
+        }
+
+        // File types and functions are loaded through autocmds as well, so that the first
+        // trigger removes the others.
+        let types = p.for_types.join(",");
+        let triggers = p.trigger_events();
+        if !types.is_empty() || !triggers.is_empty() {
+            let events = Some(("FileType", types.as_str()))
+                .filter(|_| !types.is_empty())
+                .into_iter()
+                .chain(triggers.iter().map(|(e, pattern)| (*e, pattern.as_str())))
+                .collect::<Vec<(&str, &str)>>();
+            block.push_str(&syntax.event_group(&group_name(p), &events, &load_order(p, &packs)));
+            block.push('\n');
+        }
+
+        let modules = syntax.module_stub(&group_name(p), &p.modules, &load_order(p, &packs));
+        if !modules.is_empty() {
+            block.push_str(&modules);
+            block.push('\n');
+        }
+
+        if !p.keys.is_empty() {
+            let names = load_order(p, &packs);
+            for k in &p.keys {
+                for mode in &k.modes {
+                    block.push_str(&syntax.key_stub(mode, &k.lhs, &group_name(p), &names));
+                }
             }
-            f.write_all(format!("{}\n", &buf).as_bytes())?;
+            block.push('\n');
+        }
+
+        block.push_str(&read_configs(syntax, &p.config_files(ctx, false))?);
+
+        // Start packages are loaded after every config ran, see below.
+        let after = p
+            .config_files(ctx, true)
+            .iter()
+            .filter(|f| f.is_file())
+            .map(|f| f.to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        if p.opt && !after.is_empty() {
+            block.push_str(&syntax.after_config(p.dir_name(), &after));
+            block.push('\n');
+        }
+
+        let groups = augroups(&p.path(ctx));
+        if p.opt && !groups.is_empty() {
+            block.push_str(&syntax.package_groups(p.dir_name(), &groups));
+            block.push('\n');
+        }
+
+        if !block.is_empty() {
+            f.write_all(syntax.comment(&p.name).as_bytes())?;
+            f.write_all(syntax.guard(&p.when, &block).as_bytes())?;
         }
     }
-    Ok(())
+    // Start packages come after the configs so that these run before the packages are
+    // loaded, like for the start packages vim loads.
+    let start = dependency_order(packs.iter().filter(|p| !p.opt).cloned().collect());
+    if !start.is_empty() {
+        f.write_all(syntax.comment("Start packages").as_bytes())?;
+        for p in start {
+            let mut load = format!("{}\n", syntax.ex(&format!("packadd {}", p.dir_name())));
+            load.push_str(&read_configs(syntax, &p.config_files(ctx, true))?);
+            // A package which failed to install stays in the packfile.
+            let load = syntax.guard_directory(&p.path(ctx).to_string_lossy(), &load);
+            f.write_all(syntax.guard(&p.when, &load).as_bytes())?;
+        }
+        f.write_all(b"\n")?;
+    }
+    utils::write_atomic(&ctx.plugin_file, &f)
+}
+
+/// The vim script and Lua config files in `paths` embedded in the plugin file, empty if
+/// there are none.
+fn read_configs(syntax: Syntax, paths: &[PathBuf]) -> Result<String> {
+    let mut ret = String::new();
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let mut buf = String::new();
+        File::open(path)?.read_to_string(&mut buf)?;
+        if path.extension() == Some("lua".as_ref()) {
+            ret.push_str(&syntax.lua_script(&buf));
+        } else {
+            ret.push_str(&syntax.vim_script(&buf));
+        }
+    }
+    Ok(ret)
+}
+
+/// Augroups defined by the plugin and ftplugin scripts of the package in `dir`.
+fn augroups(dir: &Path) -> Vec<String> {
+    let mut groups = Vec::new();
+    for sub in &["plugin", "ftplugin", "after/plugin", "after/ftplugin"] {
+        let files = WalkDir::new(dir.join(sub))
+            .sort_by(|a, b| a.cmp(b))
+            .into_iter()
+            .filter_map(|e| e.ok());
+        for entry in files {
+            let parse = match entry.path().extension().and_then(|e| e.to_str()) {
+                Some("vim") => vim_augroup,
+                Some("lua") => lua_augroup,
+                _ => continue,
+            };
+            let data = match fs::read_to_string(entry.path()) {
+                Ok(d) => d,
+                Err(_) => continue,
+            };
+            for name in data.lines().filter_map(parse) {
+                if !groups.iter().any(|g| g == name) {
+                    groups.push(name.to_string());
+                }
+            }
+        }
+    }
+    groups
+}
+
+/// Group defined by an `:augroup` command on a vim script line.
+fn vim_augroup(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    let cmd = words.next()?;
+    if cmd.len() < 3 || !"augroup".starts_with(cmd) {
+        return None;
+    }
+    let name = words.next()?.split('|').next()?;
+    if name.is_empty() || name.eq_ignore_ascii_case("END") {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Group created by `nvim_create_augroup` on a Lua line.
+fn lua_augroup(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("nvim_create_augroup(")?;
+    let rest = rest.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let (name, _) = rest[1..].split_once(quote)?;
+    Some(name).filter(|n| !n.is_empty())
+}
+
+/// Name of the augroup of the autocmds loading `pack`.
+fn group_name(pack: &Package) -> String {
+    let name = pack
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    format!("pack_{}", name)
+}
+
+/// Directory names of an opt package and its opt dependencies, in the order to load them.
+fn load_order<'a>(pack: &'a Package, packs: &'a [Package]) -> Vec<&'a str> {
+    opt_dependencies(pack, packs)
+        .into_iter()
+        .chain(Some(pack))
+        .map(|p| p.dir_name())
+        .collect()
+}
+
+/// Opt packages required by `pack`, directly or indirectly, in the order they should be
+/// loaded. Start packages are always loaded so they are skipped.
+pub fn opt_dependencies<'a>(pack: &Package, packs: &'a [Package]) -> Vec<&'a Package> {
+    fn visit<'a>(
+        pack: &Package,
+        packs: &'a [Package],
+        seen: &mut Vec<String>,
+        ret: &mut Vec<&'a Package>,
+    ) {
+        for name in &pack.requires {
+            if seen.contains(name) {
+                continue;
+            }
+            seen.push(name.clone());
+            if let Some(dep) = packs.iter().find(|x| &x.name == name) {
+                visit(dep, packs, seen, ret);
+                if dep.opt {
+                    ret.push(dep);
+                }
+            }
+        }
+    }
+
+    let mut seen = vec![pack.name.clone()];
+    let mut ret = Vec::new();
+    visit(pack, packs, &mut seen, &mut ret);
+    ret
+}
+
+/// Order packages so that every package comes after the packages it requires.
+pub fn dependency_order(packs: Vec<Package>) -> Vec<Package> {
+    fn visit(index: usize, packs: &[Package], seen: &mut Vec<bool>, order: &mut Vec<usize>) {
+        if seen[index] {
+            return;
+        }
+        seen[index] = true;
+        for name in &packs[index].requires {
+            if let Some(i) = packs.iter().position(|x| &x.name == name) {
+                visit(i, packs, seen, order);
+            }
+        }
+        order.push(index);
+    }
+
+    let mut seen = vec![false; packs.len()];
+    let mut order = Vec::with_capacity(packs.len());
+    for i in 0..packs.len() {
+        visit(i, &packs, &mut seen, &mut order);
+    }
+
+    let mut packs = packs
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<Option<Package>>>();
+    order.into_iter().filter_map(|i| packs[i].take()).collect()
+}
+
+/// Names of the packages requiring `name`.
+pub fn dependents<'a>(name: &str, packs: &'a [Package]) -> Vec<&'a str> {
+    packs
+        .iter()
+        .filter(|p| p.requires.iter().any(|r| r == name))
+        .map(|p| p.name.as_str())
+        .collect()
 }
 
 fn read_dir<H>(dir: &Path, mut action: H) -> Result<()>
@@ -345,27 +987,31 @@ where
     if !dir.is_dir() {
         return Ok(());
     }
-    for entry in dir.read_dir()? {
-        if let Ok(e) = entry {
-            let sub = e.path();
-            let item = match sub.file_name().iter().flat_map(|s| s.to_str()).next() {
-                None => continue,
-                Some(i) => i.to_string(),
-            };
-            if sub.is_dir() && !item.starts_with('.') {
-                action(&sub, item)?;
-            }
+    for e in dir.read_dir()?.flatten() {
+        let sub = e.path();
+        let item = match sub.file_name().iter().flat_map(|s| s.to_str()).next() {
+            None => continue,
+            Some(i) => i.to_string(),
+        };
+        if sub.is_dir() && !item.starts_with('.') {
+            action(&sub, item)?;
         }
     }
     Ok(())
 }
 
-pub fn walk_packs<F>(category: &Option<String>, start: bool, opt: bool, callback: F) -> Result<()>
+pub fn walk_packs<F>(
+    ctx: &Context,
+    category: &Option<String>,
+    start: bool,
+    opt: bool,
+    callback: F,
+) -> Result<()>
 where
     F: Fn(&str, &str, &str),
 {
-    read_dir(&PACK_DIR, |path, cate| {
-        let is_match = category.as_ref().map_or(true, |c| *c == cate);
+    read_dir(&ctx.pack_dir, |path, cate| {
+        let is_match = category.as_ref().is_none_or(|c| *c == cate);
         if !is_match {
             Ok(())
         } else {
@@ -390,25 +1036,289 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
+
+    fn context() -> Context {
+        Context::new("/vim", None)
+    }
 
     #[test]
     fn package_path_user_repo() {
         let p = Package::new("user/reponame", "", false);
-        let exp = PACK_DIR.join("start").join("reponame");
-        assert_eq!(exp, p.path());
+        let exp = Path::new("/vim/pack/opt/reponame");
+        assert_eq!(exp, p.path(&context()));
     }
 
     #[test]
     fn package_path_nouser() {
         let p = Package::new("reponame", "", false);
-        let exp = PACK_DIR.join("start").join("reponame");
-        assert_eq!(exp, p.path());
+        let exp = Path::new("/vim/pack/opt/reponame");
+        assert_eq!(exp, p.path(&context()));
+    }
+
+    #[test]
+    fn package_path_profile() {
+        let ctx = Context::new("/vim", Some("work"));
+        let p = Package::new("user/reponame", "default", false);
+        let exp = Path::new("/vim/pack/default/opt/reponame");
+        assert_eq!(exp, p.path(&ctx));
+        assert_eq!(exp, p.path(&context()));
+    }
+
+    #[test]
+    fn config_paths() {
+        let p = Package::new("user/reponame", "default", true);
+        assert_eq!(
+            p.config_path(&context()),
+            Path::new("/vim/.pack/user-reponame.vim")
+        );
+        assert_eq!(
+            p.after_config_path(&context()),
+            Path::new("/vim/.pack/user-reponame.after.vim")
+        );
+        let mut p = Package::new("/opt/plugin.vim", "default", true);
+        p.local = true;
+        assert_eq!(
+            p.config_files(&context(), true),
+            [
+                PathBuf::from("/vim/.pack/plugin.after.vim"),
+                PathBuf::from("/vim/.pack/plugin.after.lua")
+            ]
+        );
+    }
+
+    fn requiring(name: &str, opt: bool, requires: &[&str]) -> Package {
+        let mut p = Package::new(name, "", opt);
+        p.set_requires(requires.iter().map(|s| s.to_string()).collect());
+        p
+    }
+
+    #[test]
+    fn opt_dependencies_in_load_order() {
+        let packs = vec![
+            requiring("a/ext", true, &["a/base", "a/lib"]),
+            requiring("a/base", true, &["a/lib", "a/start"]),
+            requiring("a/lib", true, &["a/ext"]),
+            requiring("a/start", false, &[]),
+        ];
+        let deps = opt_dependencies(&packs[0], &packs)
+            .into_iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(deps, vec!["a/lib", "a/base"]);
+        assert_eq!(load_order(&packs[0], &packs), vec!["lib", "base", "ext"]);
+    }
+
+    #[test]
+    fn local_package_dir_name() {
+        let mut local = requiring("/home/me/plugins/my-plugin", true, &["a/lib"]);
+        local.local = true;
+        let packs = vec![local, requiring("a/lib", true, &[])];
+        assert_eq!(packs[0].dir_name(), "my-plugin");
+        assert_eq!(
+            packs[0].path(&context()),
+            Path::new("/vim/pack/opt/my-plugin")
+        );
+        assert_eq!(load_order(&packs[0], &packs), vec!["lib", "my-plugin"]);
+    }
+
+    #[test]
+    fn dependencies_ordered_first() {
+        let packs = vec![
+            requiring("a/ext", true, &["a/base"]),
+            requiring("a/other", false, &[]),
+            requiring("a/base", true, &[]),
+        ];
+        let names = dependency_order(packs)
+            .into_iter()
+            .map(|p| p.name)
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["a/base", "a/ext", "a/other"]);
+    }
+
+    #[test]
+    fn overlay_only_keeps_changes() {
+        let mut changed = Package::new("a/changed", "default", false);
+        let base = vec![
+            Package::new("a/same", "default", false),
+            changed.clone(),
+            Package::new("a/removed", "default", false),
+        ];
+        changed.set_opt(true);
+        let packs = vec![
+            Package::new("a/same", "default", false),
+            changed.clone(),
+            Package::new("a/added", "default", false),
+        ];
+        let include = Include {
+            path: String::from("base"),
+            repo: None,
+        };
+
+        let entries = overlay(vec![Entry::Include(include.clone())], &base, packs);
+        assert_eq!(entries.len(), 4);
+        match (&entries[0], &entries[1], &entries[2], &entries[3]) {
+            (Entry::Include(i), Entry::Disable(d), Entry::Package(c), Entry::Package(a)) => {
+                assert_eq!(*i, include);
+                assert_eq!(d, "a/removed");
+                assert_eq!(**c, changed);
+                assert_eq!(a.name, "a/added");
+            }
+            _ => panic!("unexpected entries: {:?}", entries),
+        }
+    }
+
+    #[test]
+    fn layers_override_and_disable() {
+        let dir = env::temp_dir().join(format!("pack-layers-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("base"),
+            "- {name: a/one, category: default, opt: false}\n\
+             - {name: a/two, category: default, opt: false}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("packfile"),
+            "- include: base\n\
+             - {name: a/one, category: lang, opt: true}\n\
+             - {name: a/two, disable: true}\n\
+             - {name: a/three, category: default, opt: false}\n",
+        )
+        .unwrap();
+
+        let mut packs = Vec::new();
+        fetch_layers(&context(), &dir.join("packfile"), &mut packs, &mut vec![]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(packs.len(), 2);
+        assert_eq!(packs[0].name, "a/one");
+        assert_eq!(packs[0].category, "lang");
+        assert!(packs[0].opt);
+        assert_eq!(packs[1].name, "a/three");
+    }
+
+    #[test]
+    fn remote_include_from_checkout() {
+        let dir = env::temp_dir().join(format!("pack-remote-include-{}", process::id()));
+        let ctx = Context::new(&dir, None);
+        fs::create_dir_all(&ctx.config_dir).unwrap();
+        fs::write(
+            &ctx.packfile,
+            "- include: {repo: me/dotfiles, path: packfile}\n\
+             - {name: a/two, category: default, opt: false}\n",
+        )
+        .unwrap();
+        let missing = fetch(&ctx).unwrap_err().to_string();
+
+        let checkout = ctx.config_dir.join("includes/me-dotfiles");
+        fs::create_dir_all(&checkout).unwrap();
+        fs::write(
+            checkout.join("packfile"),
+            "- {name: a/one, category: default, opt: false}\n",
+        )
+        .unwrap();
+        let mut file = load(&ctx).unwrap();
+        file.packs.retain(|p| p.name == "a/one");
+        file.save(&ctx).unwrap();
+        let saved = fs::read_to_string(&ctx.packfile).unwrap();
+        let packs = fetch(&ctx).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(missing.contains("me/dotfiles is not cloned"));
+        assert!(saved.contains("repo: me/dotfiles"));
+        assert!(!saved.contains("a/two"));
+        assert_eq!(packs.len(), 1);
+        assert_eq!(packs[0].name, "a/one");
+    }
+
+    #[test]
+    fn save_through_symlink() {
+        let dir = env::temp_dir().join(format!("pack-symlink-{}", process::id()));
+        let ctx = Context::new(&dir, None);
+        fs::create_dir_all(&ctx.config_dir).unwrap();
+        fs::write(dir.join("dotfiles-packfile"), "").unwrap();
+        std::os::unix::fs::symlink(dir.join("dotfiles-packfile"), &ctx.packfile).unwrap();
+
+        save(&ctx, vec![Package::new("a/one", "default", false)]).unwrap();
+        let link = fs::symlink_metadata(&ctx.packfile).unwrap();
+        let packs = fetch(&ctx).unwrap();
+        let target = fs::read_to_string(dir.join("dotfiles-packfile")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(link.file_type().is_symlink());
+        assert_eq!(packs.len(), 1);
+        assert!(target.contains("a/one"));
+    }
+
+    #[test]
+    fn profile_loads_no_default_packages() {
+        let dir = env::temp_dir().join(format!("pack-default-start-{}", process::id()));
+        let ctx = Context::new(&dir, None);
+        let minimal = Context::new(&dir, Some("minimal"));
+        let pack = Package::new("a/one", "default", false);
+        fs::create_dir_all(dir.join("pack/default/start/one")).unwrap();
+        save(&ctx, vec![pack.clone()]).unwrap();
+        save(&minimal, vec![Package::new("a/two", "default", false)]).unwrap();
+
+        generate(&ctx).unwrap();
+        generate(&minimal).unwrap();
+        let moved = pack.path(&ctx).is_dir();
+        let start = fs::read_dir(dir.join("pack/default/start"))
+            .unwrap()
+            .count();
+        let plugin = fs::read_to_string(&ctx.plugin_file).unwrap();
+        let other = fs::read_to_string(&minimal.plugin_file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(moved);
+        assert_eq!(start, 0);
+        let guard = plugin.find("!=# 'default'").unwrap();
+        assert!(plugin.find("packadd one").unwrap() > guard);
+        assert!(!other.contains("packadd one"));
+    }
+
+    #[test]
+    fn package_augroups() {
+        let dir = env::temp_dir().join(format!("pack-augroups-{}", process::id()));
+        fs::create_dir_all(dir.join("plugin")).unwrap();
+        fs::create_dir_all(dir.join("after/ftplugin")).unwrap();
+        fs::write(
+            dir.join("plugin/a.vim"),
+            "augroup Foo\n  autocmd!\naugroup END\n  aug Bar| au! | aug END\naugroup! Old\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("plugin/b.lua"),
+            "local g = vim.api.nvim_create_augroup( 'Baz', { clear = true })\n",
+        )
+        .unwrap();
+        fs::write(dir.join("after/ftplugin/go.vim"), "augroup Foo\n").unwrap();
+
+        let groups = augroups(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(groups, vec!["Foo", "Bar", "Baz"]);
     }
 
     #[test]
     fn package_path_repo_slash() {
         let p = Package::new("user/reponame/with_slash", "", false);
-        let exp = PACK_DIR.join("start").join("reponame/with_slash");
-        assert_eq!(exp, p.path());
+        let exp = Path::new("/vim/pack/opt/reponame/with_slash");
+        assert_eq!(exp, p.path(&context()));
+    }
+
+    #[test]
+    fn lazy_triggers_round_trip() {
+        let docs = YamlLoader::load_from_str(
+            "{name: junegunn/fzf, category: default, opt: true, on_func: 'fzf#', \
+             on_module: [fzf, fzf-lua], event: InsertEnter}",
+        )
+        .unwrap();
+        let p = Package::from_yaml(&docs[0]).unwrap();
+        assert_eq!(p.funcs, vec!["fzf#"]);
+        assert_eq!(p.modules, vec!["fzf", "fzf-lua"]);
+        assert_eq!(p.events, vec!["InsertEnter"]);
+        assert_eq!(Package::from_yaml(&p.clone().into_yaml()).unwrap(), p);
     }
 }
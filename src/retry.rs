@@ -0,0 +1,125 @@
+//! Packages which failed in the last install or update, retried by `pack retry`.
+
+use crate::context::{Context, Target};
+use crate::package::Package;
+use crate::task::TaskType;
+use crate::utils;
+use crate::{Error, Result};
+
+use std::fs;
+use std::io;
+
+use yaml_rust::yaml::Hash;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
+
+/// Failed packages of a run and the targets they failed for. The packages keep their
+/// whole entry, as new packages which failed to install are not saved to the packfile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastRun {
+    pub task_type: TaskType,
+    pub failures: Vec<(Target, Package)>,
+}
+
+impl LastRun {
+    /// Packages which failed for `target`.
+    pub fn failures_of(&self, target: Target) -> Vec<Package> {
+        self.failures
+            .iter()
+            .filter(|(t, _)| *t == target)
+            .map(|(_, pack)| pack.clone())
+            .collect()
+    }
+}
+
+/// Record `run` in the last run file of `ctx`, replacing the previous run.
+pub fn save(ctx: &Context, run: &LastRun) -> Result<()> {
+    let path = ctx.last_run_file();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let failures = run
+        .failures
+        .iter()
+        .map(|(target, pack)| {
+            let mut doc = Hash::new();
+            doc.insert(Yaml::from_str("target"), Yaml::from_str(target.name()));
+            doc.insert(Yaml::from_str("package"), pack.clone().into_yaml());
+            Yaml::Hash(doc)
+        })
+        .collect();
+    let mut doc = Hash::new();
+    doc.insert(Yaml::from_str("type"), Yaml::from_str(run.task_type.name()));
+    doc.insert(Yaml::from_str("failures"), Yaml::Array(failures));
+
+    let mut data = String::from("# Packages which failed in the last run, for `pack retry`.\n");
+    {
+        let mut emitter = YamlEmitter::new(&mut data);
+        emitter.dump(&Yaml::Hash(doc))?;
+    }
+    data.push('\n');
+    utils::write_atomic(path, data.as_bytes())
+}
+
+/// The last run recorded for `ctx`, `None` if there was none.
+pub fn load(ctx: &Context) -> Result<Option<LastRun>> {
+    let data = match fs::read_to_string(ctx.last_run_file()) {
+        Ok(d) => d,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let docs = YamlLoader::load_from_str(&data)?;
+    let doc = docs.first().ok_or(Error::Format)?;
+    let task_type = doc["type"]
+        .as_str()
+        .and_then(TaskType::from_name)
+        .ok_or(Error::Format)?;
+    let mut failures = Vec::new();
+    for failure in doc["failures"].as_vec().ok_or(Error::Format)? {
+        let target = failure["target"]
+            .as_str()
+            .and_then(Target::from_name)
+            .ok_or(Error::Format)?;
+        failures.push((target, Package::from_yaml(&failure["package"])?));
+    }
+    Ok(Some(LastRun {
+        task_type,
+        failures,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn save_and_load() {
+        let dir = env::temp_dir().join(format!("pack-retry-{}", process::id()));
+        let ctx = Context::new(&dir, None);
+        assert_eq!(load(&ctx).unwrap(), None);
+
+        let mut local = Package::new("/home/me/my plugin", "lang", true);
+        local.local = true;
+        local.set_types(vec![String::from("rust")]);
+        local.when.editor = Some(String::from("neovim"));
+        let run = LastRun {
+            task_type: TaskType::Install,
+            failures: vec![
+                (
+                    Target::Vim,
+                    Package::new("tpope/vim-fugitive", "default", false),
+                ),
+                (Target::Neovim, local.clone()),
+            ],
+        };
+        save(&ctx, &run).unwrap();
+        let loaded = load(&ctx).unwrap();
+        let profile = Context::new(&dir, Some("work")).last_run_file();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, Some(run.clone()));
+        assert_eq!(run.failures_of(Target::Neovim), vec![local]);
+        assert_eq!(profile, dir.join(".pack/profiles/work/last-run"));
+    }
+}
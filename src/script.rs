@@ -0,0 +1,664 @@
+use crate::condition::Condition;
+use crate::context::{Target, DEFAULT_PROFILE};
+use crate::utils::{lua_string, vim_string};
+
+const VIM_HEADER: &str = "\" Generated by pack. DO NOT EDIT!
+
+scriptencoding utf-8
+";
+const VIM_FUNCTIONS: &str = r#"let s:cmds = {}
+let s:after = {}
+let s:groups = {}
+
+function! s:do_cmd(cmd, bang, start, end, args)
+    exec printf('%s%s%s %s', (a:start == a:end ? '' : (a:start.','.a:end)), a:cmd, a:bang, a:args)
+endfunction
+
+function! s:load_packs(group, names, event)
+    if exists('#'.a:group)
+        execute 'autocmd! '.a:group
+    endif
+    for [cmd, load] in items(s:cmds)
+        if load[0] ==# a:group
+            execute 'delcommand '.cmd
+            call remove(s:cmds, cmd)
+        endif
+    endfor
+    let groups = ['filetypeplugin', 'filetypeindent', 'syntaxset']
+    for name in a:names
+        execute 'packadd '.name
+        for file in has_key(s:after, name) ? remove(s:after, name) : []
+            execute (file =~# '\.lua$' ? 'luafile ' : 'source ').fnameescape(file)
+        endfor
+        let groups += has_key(s:groups, name) ? remove(s:groups, name) : []
+    endfor
+    for group in empty(a:event) ? [] : groups
+        if exists('#'.group.'#'.substitute(a:event, ' ', '#', ''))
+            execute 'doautocmd <nomodeline> '.group.' '.a:event
+        endif
+    endfor
+endfunction
+
+function! s:ftdetect(dir)
+    augroup filetypedetect
+        for f in globpath(&packpath, a:dir.'/ftdetect/*.vim', 0, 1)
+            execute 'source '.fnameescape(f)
+        endfor
+    augroup END
+endfunction
+
+function! s:load_cmd(cmd, bang, start, end, args)
+    call call('s:load_packs', s:cmds[a:cmd] + [''])
+    call s:do_cmd(a:cmd, a:bang, a:start, a:end, a:args)
+endfunction
+
+function! s:complete_cmd(lead, line, pos)
+    let cmd = matchstr(a:line, '\u\w*')
+    if has_key(s:cmds, cmd)
+        call call('s:load_packs', s:cmds[cmd] + [''])
+    endif
+    return getcompletion(a:line[: a:pos - 1], 'cmdline')
+endfunction
+
+function! s:load_map(lhs, mode, group, names, prefix)
+    execute a:mode.'unmap '.a:lhs
+    call s:load_packs(a:group, a:names, '')
+    let extra = ''
+    while 1
+        let c = getchar(0)
+        if c == 0
+            break
+        endif
+        let extra .= nr2char(c)
+    endwhile
+    let prefix = a:prefix
+    if a:mode !=# 'i'
+        let prefix = (v:count ? v:count : '').'"'.v:register.prefix
+        if a:mode ==# 'o'
+            let prefix = (v:operator ==# 'c' ? "\<Esc>" : '').prefix.v:operator
+        endif
+    endif
+    call feedkeys(prefix, 'n')
+    call feedkeys(s:keys(a:lhs).extra)
+endfunction
+
+function! s:keys(lhs)
+    let lhs = substitute(a:lhs, '\c<leader>', escape(get(g:, 'mapleader', '\'), '\&'), 'g')
+    let lhs = substitute(lhs, '\c<localleader>', escape(get(g:, 'maplocalleader', '\'), '\&'), 'g')
+    return eval('"'.substitute(escape(lhs, '\"'), '<[^<>]\+>', '\\&', 'g').'"')
+endfunction
+"#;
+
+const LUA_HEADER: &str = "-- Generated by pack. DO NOT EDIT!
+";
+const LUA_FUNCTIONS: &str = "local commands = {}
+local modules = {}
+local after = {}
+local groups = {}
+
+local function do_cmd(cmd, opts)
+  local range = opts.line1 == opts.line2 and '' or (opts.line1 .. ',' .. opts.line2)
+  vim.cmd(string.format('%s%s%s %s', range, cmd, opts.bang and '!' or '', opts.args))
+end
+
+local function load_packs(group, names, event)
+  pcall(vim.api.nvim_del_augroup_by_name, group)
+  for cmd, entry in pairs(commands) do
+    if entry.group == group then
+      vim.api.nvim_del_user_command(cmd)
+      commands[cmd] = nil
+    end
+  end
+  for module, entry in pairs(modules) do
+    if entry.group == group then
+      modules[module] = nil
+    end
+  end
+  local refire = { 'filetypeplugin', 'filetypeindent', 'syntaxset' }
+  for _, name in ipairs(names) do
+    vim.cmd('packadd ' .. name)
+    for _, file in ipairs(after[name] or {}) do
+      vim.cmd('source ' .. vim.fn.fnameescape(file))
+    end
+    after[name] = nil
+    vim.list_extend(refire, groups[name] or {})
+    groups[name] = nil
+  end
+  if event == '' then
+    return
+  end
+  for _, group in ipairs(refire) do
+    if vim.fn.exists('#' .. group .. '#' .. (event:gsub(' ', '#'))) == 1 then
+      vim.cmd('doautocmd <nomodeline> ' .. group .. ' ' .. event)
+    end
+  end
+end
+
+local function ftdetect(dir)
+  vim.cmd('augroup filetypedetect')
+  for _, ext in ipairs({ 'vim', 'lua' }) do
+    for _, f in ipairs(vim.fn.globpath(vim.o.packpath, dir .. '/ftdetect/*.' .. ext, false, true)) do
+      vim.cmd('source ' .. vim.fn.fnameescape(f))
+    end
+  end
+  vim.cmd('augroup END')
+end
+
+local function load_cmd(cmd, opts)
+  local entry = commands[cmd]
+  load_packs(entry.group, entry.names, '')
+  do_cmd(cmd, opts)
+end
+
+local function complete_cmd(cmd, line, pos)
+  local entry = commands[cmd]
+  if entry then
+    load_packs(entry.group, entry.names, '')
+  end
+  return vim.fn.getcompletion(line:sub(1, pos), 'cmdline')
+end
+
+local function load_map(mode, lhs, group, names)
+  vim.keymap.del(mode, lhs)
+  load_packs(group, names, '')
+  local keys = vim.api.nvim_replace_termcodes('<Ignore>' .. lhs, true, true, true)
+  vim.api.nvim_feedkeys(keys, 'im', false)
+end
+
+table.insert(package.loaders, 2, function(module)
+  for name, entry in pairs(modules) do
+    if module == name or vim.startswith(module, name .. '.') then
+      load_packs(entry.group, entry.names, '')
+      return
+    end
+  end
+end)
+";
+
+/// Language of the generated plugin file. Vim gets vim script, neovim gets a Lua loader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Syntax {
+    Vim,
+    Lua,
+}
+
+impl Syntax {
+    pub fn for_target(target: Target) -> Syntax {
+        match target {
+            Target::Vim => Syntax::Vim,
+            Target::Neovim => Syntax::Lua,
+        }
+    }
+
+    /// Start of the plugin file: only the file of the active profile is run. The profile
+    /// is selected with `g:pack_profile` or `$PACK_PROFILE`.
+    pub fn header(self, profile: &str) -> String {
+        match self {
+            Syntax::Vim => format!(
+                "{}\n\
+                 if get(g:, 'pack_profile', empty($PACK_PROFILE) ? '{default}' : $PACK_PROFILE) !=# '{}'\n\
+                 \x20   finish\n\
+                 endif\n\n{}\n\n",
+                VIM_HEADER,
+                profile,
+                VIM_FUNCTIONS,
+                default = DEFAULT_PROFILE
+            ),
+            Syntax::Lua => format!(
+                "{}\n\
+                 local profile = vim.g.pack_profile or vim.env.PACK_PROFILE\n\
+                 if profile == nil or profile == '' then\n\
+                 \x20 profile = {default}\n\
+                 end\n\
+                 if profile ~= {} then\n\
+                 \x20 return\n\
+                 end\n\n{}\n",
+                LUA_HEADER,
+                lua_string(profile),
+                LUA_FUNCTIONS,
+                default = lua_string(DEFAULT_PROFILE)
+            ),
+        }
+    }
+
+    pub fn comment(self, text: &str) -> String {
+        match self {
+            Syntax::Vim => format!("\" {}\n", text),
+            Syntax::Lua => format!("-- {}\n", text),
+        }
+    }
+
+    /// Statement running the ex command `cmd`.
+    pub fn ex(self, cmd: &str) -> String {
+        match self {
+            Syntax::Vim => cmd.to_string(),
+            Syntax::Lua => format!("vim.cmd({})", lua_string(cmd)),
+        }
+    }
+
+    /// Define the command `cmd` loading the packages `names`, together with the other
+    /// triggers in `group`, and then running the command the packages define. Completing
+    /// the arguments of the command loads the packages as well and completes them like the
+    /// real command. The stubs of a group are deleted before the packages are loaded, so
+    /// they never shadow the real commands.
+    pub fn command_stub(self, group: &str, cmd: &str, names: &[&str]) -> String {
+        match self {
+            Syntax::Vim => format!(
+                "let s:cmds[{quoted}] = [{}, [{}]]\n\
+                 command! -nargs=* -range -bang -complete=customlist,s:complete_cmd {cmd} \
+                 call s:load_cmd({quoted}, \"<bang>\", <line1>, <line2>, <q-args>)\n",
+                vim_string(group),
+                vim_list(names),
+                cmd = cmd,
+                quoted = vim_string(cmd)
+            ),
+            Syntax::Lua => format!(
+                "commands[{cmd}] = {{ group = {}, names = {{ {} }} }}\n\
+                 vim.api.nvim_create_user_command({cmd}, function(opts) load_cmd({cmd}, opts) end, {{\n\
+                 \x20 nargs = '*',\n\
+                 \x20 range = true,\n\
+                 \x20 bang = true,\n\
+                 \x20 complete = function(_, line, pos) return complete_cmd({cmd}, line, pos) end,\n\
+                 }})\n",
+                lua_string(group),
+                lua_list(names),
+                cmd = lua_string(cmd)
+            ),
+        }
+    }
+
+    /// Run the ftdetect scripts of the opt package in `dir`, relative to 'packpath', like
+    /// `:packadd` would.
+    pub fn ftdetect(self, dir: &str) -> String {
+        match self {
+            Syntax::Vim => format!("call s:ftdetect({})\n", vim_string(dir)),
+            Syntax::Lua => format!("ftdetect({})\n", lua_string(dir)),
+        }
+    }
+
+    /// Augroup `group` loading the packages `names` on the first of the `events`, given as
+    /// event and pattern, and firing the event again for the autocmds of the packages. Only
+    /// the augroups of the packages and the ones running file type scripts are fired, see
+    /// `package_groups`.
+    pub fn event_group(self, group: &str, events: &[(&str, &str)], names: &[&str]) -> String {
+        // Buffer events, like `FileType` for the ftplugin and syntax scripts of the packages,
+        // are fired again for the current buffer, user events by name. Vim calls an
+        // undefined function again by itself once the packages are loaded.
+        let refire = |event: &str, pattern: &str| {
+            if event.eq_ignore_ascii_case("User") {
+                format!("{} {}", event, pattern)
+            } else if event.eq_ignore_ascii_case("FuncUndefined") {
+                String::new()
+            } else {
+                event.to_string()
+            }
+        };
+        match self {
+            Syntax::Vim => {
+                let names = vim_list(names);
+                let mut out = format!("augroup {}\n    autocmd!\n", group);
+                for (event, pattern) in events {
+                    out.push_str(&format!(
+                        "    autocmd {} {} call s:load_packs('{}', [{}], {})\n",
+                        event,
+                        pattern,
+                        group,
+                        names,
+                        vim_string(&refire(event, pattern))
+                    ));
+                }
+                out.push_str("augroup END\n");
+                out
+            }
+            Syntax::Lua => {
+                let names = lua_list(names);
+                let mut out = format!(
+                    "vim.api.nvim_create_augroup({}, {{ clear = true }})\n",
+                    lua_string(group)
+                );
+                for (event, pattern) in events {
+                    // Several patterns are given as a table, like file types.
+                    let patterns = pattern.split(',').collect::<Vec<&str>>();
+                    let matches = if patterns.len() > 1 {
+                        format!("{{ {} }}", lua_list(&patterns))
+                    } else {
+                        lua_string(pattern)
+                    };
+                    out.push_str(&format!(
+                        "vim.api.nvim_create_autocmd({}, {{ group = {group}, pattern = {}, \
+                         callback = function() load_packs({group}, {{ {} }}, {}) end }})\n",
+                        lua_string(event),
+                        matches,
+                        names,
+                        lua_string(&refire(event, pattern)),
+                        group = lua_string(group)
+                    ));
+                }
+                out
+            }
+        }
+    }
+
+    /// Load the packages `names` the first time one of the Lua `modules`, or a submodule of
+    /// it, is required, together with the other triggers in `group`. Vim has no Lua
+    /// loader, so nothing is generated for it.
+    pub fn module_stub(self, group: &str, modules: &[String], names: &[&str]) -> String {
+        match self {
+            Syntax::Vim => String::new(),
+            Syntax::Lua => {
+                let names = lua_list(names);
+                modules
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "modules[{}] = {{ group = {}, names = {{ {} }} }}\n",
+                            lua_string(m),
+                            lua_string(group),
+                            names
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Source the config files `paths` right after the package in directory `name` is
+    /// loaded by a lazy trigger.
+    pub fn after_config(self, name: &str, paths: &[String]) -> String {
+        let paths = paths.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+        match self {
+            Syntax::Vim => format!(
+                "let s:after[{}] = [{}]\n",
+                vim_string(name),
+                vim_list(&paths)
+            ),
+            Syntax::Lua => format!("after[{}] = {{ {} }}\n", lua_string(name), lua_list(&paths)),
+        }
+    }
+
+    /// Record the augroups `groups` defined by the package in directory `name`, which are
+    /// fired again for the event loading the package.
+    pub fn package_groups(self, name: &str, groups: &[String]) -> String {
+        let groups = groups.iter().map(|g| g.as_str()).collect::<Vec<&str>>();
+        match self {
+            Syntax::Vim => format!(
+                "let s:groups[{}] = [{}]\n",
+                vim_string(name),
+                vim_list(&groups)
+            ),
+            Syntax::Lua => format!(
+                "groups[{}] = {{ {} }}\n",
+                lua_string(name),
+                lua_list(&groups)
+            ),
+        }
+    }
+
+    /// Map `lhs` in `mode` to load the packages `names`, together with the other triggers in
+    /// `group`, then replay the keys.
+    pub fn key_stub(self, mode: &str, lhs: &str, group: &str, names: &[&str]) -> String {
+        match self {
+            Syntax::Vim => {
+                let names = names
+                    .iter()
+                    .map(|n| map_string(n))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                let (enter, prefix) = match mode {
+                    "i" => ("<C-\\><C-O>:", ""),
+                    "v" | "x" => (":<C-U>", "gv"),
+                    _ => (":<C-U>", ""),
+                };
+                format!(
+                    "{mode}noremap <silent> {lhs} {enter}call <SID>load_map({}, '{mode}', {}, [{}], '{}')<CR>\n",
+                    map_string(lhs),
+                    map_string(group),
+                    names,
+                    prefix,
+                    mode = mode,
+                    lhs = lhs,
+                    enter = enter
+                )
+            }
+            Syntax::Lua => format!(
+                "vim.keymap.set({mode}, {lhs}, function() load_map({mode}, {lhs}, {}, {{ {} }}) end, \
+                 {{ silent = true }})\n",
+                lua_string(group),
+                lua_list(names),
+                mode = lua_string(mode),
+                lhs = lua_string(lhs)
+            ),
+        }
+    }
+
+    /// Embed a vim script, like a package config file.
+    pub fn vim_script(self, script: &str) -> String {
+        match self {
+            Syntax::Vim => format!("{}\n", script),
+            Syntax::Lua => {
+                let script = script.trim_end();
+                let mut level = String::new();
+                while script.contains(&format!("]{}]", level)) {
+                    level.push('=');
+                }
+                format!("vim.cmd([{l}[\n{}\n]{l}])\n\n", script, l = level)
+            }
+        }
+    }
+
+    /// Embed a Lua script, like a Lua package config file. Its locals are kept to it.
+    pub fn lua_script(self, script: &str) -> String {
+        let script = script.trim_end();
+        match self {
+            Syntax::Vim => {
+                let mut marker = String::from("EOF");
+                while script.lines().any(|l| l.trim() == marker) {
+                    marker.push('_');
+                }
+                format!("lua << {m}\n{}\n{m}\n\n", script, m = marker)
+            }
+            Syntax::Lua => format!("do\n{}\nend\n\n", script),
+        }
+    }
+
+    /// Wrap `script` so that it only runs when the runtime conditions of `cond` are met.
+    /// Commands like `:command` and `:autocmd` take `|` as part of their arguments, so the
+    /// guard is never put on a single line.
+    pub fn guard(self, cond: &Condition, script: &str) -> String {
+        let expr = match self {
+            Syntax::Vim => cond.vim_expr(),
+            Syntax::Lua => cond.lua_expr(),
+        };
+        match expr {
+            None => script.to_string(),
+            Some(expr) => self.wrap_if(&expr, script),
+        }
+    }
+
+    /// Wrap `script` so that it only runs when the directory `path` exists, like the
+    /// directory of a package which may have failed to install.
+    pub fn guard_directory(self, path: &str, script: &str) -> String {
+        let expr = match self {
+            Syntax::Vim => format!("isdirectory({})", vim_string(path)),
+            Syntax::Lua => format!("vim.fn.isdirectory({}) == 1", lua_string(path)),
+        };
+        self.wrap_if(&expr, script)
+    }
+
+    fn wrap_if(self, expr: &str, script: &str) -> String {
+        let end = &script[script.trim_end().len()..];
+        let (open, close) = match self {
+            Syntax::Vim => (format!("if {}", expr), "endif"),
+            Syntax::Lua => (format!("if {} then", expr), "end"),
+        };
+        format!("{}\n{}\n{}{}", open, script.trim_end(), close, end)
+    }
+}
+
+/// Items of a vim script list, without the brackets.
+fn vim_list(items: &[&str]) -> String {
+    items
+        .iter()
+        .map(|n| vim_string(n))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Items of a Lua table, without the braces.
+fn lua_list(items: &[&str]) -> String {
+    items
+        .iter()
+        .map(|n| lua_string(n))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Quote a string for vim script in the right-hand side of a mapping, where key notation
+/// and `|` would be interpreted.
+fn map_string(s: &str) -> String {
+    format!(
+        "'{}'",
+        s.replace('\'', "''")
+            .replace('<', "<lt>")
+            .replace('|', "<Bar>")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_runtime_conditions() {
+        let mut cond = Condition::default();
+        assert_eq!(Syntax::Vim.guard(&cond, "packadd b"), "packadd b");
+        cond.has.push(String::from("python3"));
+        assert_eq!(
+            Syntax::Vim.guard(&cond, "packadd b"),
+            "if has('python3')\npackadd b\nendif"
+        );
+        assert_eq!(
+            Syntax::Vim.guard(&cond, "let g:a = 1\nlet g:b = 2\n\n"),
+            "if has('python3')\nlet g:a = 1\nlet g:b = 2\nendif\n\n"
+        );
+        assert_eq!(
+            Syntax::Lua.guard(&cond, "vim.cmd('packadd b')\n"),
+            "if vim.fn.has('python3') == 1 then\nvim.cmd('packadd b')\nend\n"
+        );
+        assert_eq!(
+            Syntax::Vim.guard_directory("/vim/pack/a/opt/b", "packadd b\n"),
+            "if isdirectory('/vim/pack/a/opt/b')\npackadd b\nendif\n"
+        );
+    }
+
+    #[test]
+    fn lua_statements() {
+        assert_eq!(
+            Syntax::Lua.vim_script("let g:a = ']]'\n"),
+            "vim.cmd([=[\nlet g:a = ']]'\n]=])\n\n"
+        );
+        assert_eq!(
+            Syntax::Vim.lua_script("local a = [[\nEOF\n]]\n"),
+            "lua << EOF_\nlocal a = [[\nEOF\n]]\nEOF_\n\n"
+        );
+        assert_eq!(
+            Syntax::Lua.lua_script("local a = 1\n"),
+            "do\nlocal a = 1\nend\n\n"
+        );
+        assert_eq!(
+            Syntax::Vim.after_config("a", &["/a.vim".to_string(), "/a.lua".to_string()]),
+            "let s:after['a'] = ['/a.vim', '/a.lua']\n"
+        );
+        assert_eq!(
+            Syntax::Lua.package_groups("a", &["AGroup".to_string()]),
+            "groups['a'] = { 'AGroup' }\n"
+        );
+        assert_eq!(
+            Syntax::Lua.ftdetect("pack/lang/opt/vim-go"),
+            "ftdetect('pack/lang/opt/vim-go')\n"
+        );
+        assert_eq!(Syntax::Lua.ex("packadd a"), "vim.cmd('packadd a')");
+    }
+
+    #[test]
+    fn event_groups() {
+        let events = [
+            ("InsertEnter", "*"),
+            ("User", "Ready"),
+            ("FuncUndefined", "fzf#*"),
+        ];
+        assert_eq!(
+            Syntax::Vim.event_group("pack_a_b", &events, &["b"]),
+            "augroup pack_a_b
+    autocmd!
+    autocmd InsertEnter * call s:load_packs('pack_a_b', ['b'], 'InsertEnter')
+    autocmd User Ready call s:load_packs('pack_a_b', ['b'], 'User Ready')
+    autocmd FuncUndefined fzf#* call s:load_packs('pack_a_b', ['b'], '')
+augroup END
+"
+        );
+        assert_eq!(
+            Syntax::Lua.event_group("pack_a_b", &[("FileType", "go,gomod"), events[1]], &["b"]),
+            "vim.api.nvim_create_augroup('pack_a_b', { clear = true })
+vim.api.nvim_create_autocmd('FileType', { group = 'pack_a_b', pattern = { 'go', 'gomod' }, \
+callback = function() load_packs('pack_a_b', { 'b' }, 'FileType') end })
+vim.api.nvim_create_autocmd('User', { group = 'pack_a_b', pattern = 'Ready', \
+callback = function() load_packs('pack_a_b', { 'b' }, 'User Ready') end })
+"
+        );
+    }
+
+    #[test]
+    fn module_stubs() {
+        let modules = ["telescope".to_string()];
+        assert_eq!(Syntax::Vim.module_stub("pack_a_b", &modules, &["b"]), "");
+        assert_eq!(
+            Syntax::Lua.module_stub("pack_a_b", &modules, &["a", "b"]),
+            "modules['telescope'] = { group = 'pack_a_b', names = { 'a', 'b' } }\n"
+        );
+    }
+
+    #[test]
+    fn key_stubs() {
+        assert_eq!(
+            Syntax::Vim.key_stub(
+                "x",
+                "<Plug>(EasyAlign)",
+                "pack_a",
+                &["lib", "vim-easy-align"]
+            ),
+            "xnoremap <silent> <Plug>(EasyAlign) :<C-U>call <SID>load_map(\
+             '<lt>Plug>(EasyAlign)', 'x', 'pack_a', ['lib', 'vim-easy-align'], 'gv')<CR>\n"
+        );
+        assert_eq!(
+            Syntax::Vim.key_stub("i", "<C-j>", "pack_a", &["a"]),
+            "inoremap <silent> <C-j> <C-\\><C-O>:call <SID>load_map('<lt>C-j>', 'i', 'pack_a', ['a'], '')<CR>\n"
+        );
+        assert_eq!(
+            Syntax::Lua.key_stub("n", "ga", "pack_a", &["a"]),
+            "vim.keymap.set('n', 'ga', function() load_map('n', 'ga', 'pack_a', { 'a' }) end, \
+             { silent = true })\n"
+        );
+    }
+
+    #[test]
+    fn command_stubs() {
+        assert_eq!(
+            Syntax::Vim.command_stub("pack_a", "Gitv", &["gitv"]),
+            "let s:cmds['Gitv'] = ['pack_a', ['gitv']]\n\
+             command! -nargs=* -range -bang -complete=customlist,s:complete_cmd Gitv \
+             call s:load_cmd('Gitv', \"<bang>\", <line1>, <line2>, <q-args>)\n"
+        );
+        assert_eq!(
+            Syntax::Lua.command_stub("pack_a", "Gitv", &["gitv"]),
+            "commands['Gitv'] = { group = 'pack_a', names = { 'gitv' } }
+vim.api.nvim_create_user_command('Gitv', function(opts) load_cmd('Gitv', opts) end, {
+  nargs = '*',
+  range = true,
+  bang = true,
+  complete = function(_, line, pos) return complete_cmd('Gitv', line, pos) end,
+})
+"
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use crate::echo;
+use crate::context::Context;
+use crate::helptags;
 use crate::package::Package;
-use crate::utils::Spinner;
 use crate::Error;
 use crate::Result;
 
@@ -9,114 +9,294 @@ use crossbeam_utils::sync::WaitGroup;
 use signal_hook::iterator::Signals;
 use std::fs;
 use std::io;
-use std::process;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use termion::{color, terminal_size};
+use std::time::{Duration, Instant};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TaskType {
     Install,
     Update,
+    /// Check for updates without changing the packages
+    Outdated,
+}
+
+impl TaskType {
+    pub fn from_name(name: &str) -> Option<TaskType> {
+        match name {
+            "install" => Some(TaskType::Install),
+            "update" => Some(TaskType::Update),
+            "outdated" => Some(TaskType::Outdated),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskType::Install => "install",
+            TaskType::Update => "update",
+            TaskType::Outdated => "outdated",
+        }
+    }
+}
+
+/// What syncing a package did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Installed,
+    Updated,
+    Unchanged,
+    /// The package has updates which were not applied
+    Outdated,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Installed => "installed",
+            Outcome::Updated => "updated",
+            Outcome::Unchanged => "unchanged",
+            Outcome::Outdated => "outdated",
+        }
+    }
+}
+
+/// How the task of a package ended.
+#[derive(Debug, Clone)]
+pub enum Status {
+    Done(Outcome),
+    Skipped(String),
+    Failed { error: String, fatal: bool },
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskResult {
+    pub pack: Package,
+    pub status: Status,
+    pub duration: Duration,
+}
+
+/// Results of a `TaskManager` run.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub tasks: Vec<TaskResult>,
+    pub duration: Duration,
+}
+
+impl Report {
+    /// Packages which failed, as they were synced.
+    pub fn failures(&self) -> Vec<Package> {
+        self.tasks
+            .iter()
+            .filter(|t| matches!(t.status, Status::Failed { .. }))
+            .map(|t| t.pack.clone())
+            .collect()
+    }
+
+    /// Number of packages which failed, fatally or not.
+    pub fn failed(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| matches!(t.status, Status::Failed { .. }))
+            .count()
+    }
+
+    /// Number of packages which were synced.
+    pub fn done(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|t| matches!(t.status, Status::Done(_)))
+            .count()
+    }
+}
+
+/// State change of a package task.
+pub enum Event<'a> {
+    /// The package started syncing
+    Syncing,
+    /// The package is being built
+    Building,
+    /// The package is synced
+    Done(Outcome),
+    /// The task failed. A fatal failure leaves the package unusable, like a failed install
+    Failed { error: &'a Error, fatal: bool },
+    /// The package is not synced
+    Skipped(&'a str),
+    /// Something about the package needs attention, like its help files
+    Warning(&'a str),
+}
+
+/// Receives the progress of a `TaskManager` run. Events of different packages are
+/// reported concurrently.
+pub trait Reporter: Send + Sync {
+    /// Called before the first task starts with the number of packages to sync.
+    fn begin(&self, _total: usize) {}
+
+    fn event(&self, pack: &Package, event: Event);
+
+    /// Called after all tasks ended with their results.
+    fn end(&self, _report: &Report) {}
 }
 
 pub struct TaskManager {
+    ctx: Context,
     task_type: TaskType,
     packs: Vec<Package>,
+    skipped: Vec<(Package, String)>,
     thread_num: usize,
 }
 
 impl TaskManager {
-    pub fn new(task_type: TaskType, thread_num: usize) -> TaskManager {
+    pub fn new(ctx: &Context, task_type: TaskType, thread_num: usize) -> TaskManager {
         TaskManager {
-            task_type: task_type,
+            ctx: ctx.clone(),
+            task_type,
             packs: Vec::new(),
+            skipped: Vec::new(),
             thread_num,
         }
     }
 
     pub fn add(&mut self, pack: Package) {
+        if !pack.when.matches_system() {
+            self.skip(pack, "conditions not met");
+            return;
+        }
+        if !pack.when.matches_target(self.ctx.target) {
+            let reason = format!("not used by {}", self.ctx.target.name());
+            self.skip(pack, reason);
+            return;
+        }
         self.packs.push(pack);
     }
 
-    /// returns true on success otherwise false
-    fn update<F>(pack: &Package, line: u16, func: F) -> bool
+    /// Report `pack` as skipped without syncing it.
+    pub fn skip<T: Into<String>>(&mut self, pack: Package, reason: T) {
+        self.skipped.push((pack, reason.into()));
+    }
+
+    fn update<F>(
+        ctx: &Context,
+        task_type: TaskType,
+        pack: &Package,
+        reporter: &dyn Reporter,
+        func: F,
+    ) -> Status
     where
-        F: Fn(&Package) -> (Result<()>, bool),
+        F: Fn(&Context, &Package) -> (Result<Outcome>, bool),
     {
-        let msg = format!(" [{}]", &pack.name);
-        let pos = msg.len() as u16;
-        echo::message(line, 0, &format!("    {} syncing", &msg));
-
-        const MSG_MARGIN: u16 = 5;
-        const SIGN_MARGIN: u16 = 3;
-
-        macro_rules! print_err {
-            ($err:expr) => {
-                let msg = format!("{}", $err);
-                echo::character(line, SIGN_MARGIN, '✗', color::Red);
-                echo::inline_message(line, MSG_MARGIN + pos, &msg);
-            };
+        reporter.event(pack, Event::Syncing);
+
+        let outcome = match func(ctx, pack) {
+            (Ok(o), _) => o,
+            (Err(e @ Error::SkipLocal), _) => {
+                let reason = e.to_string();
+                reporter.event(pack, Event::Skipped(&reason));
+                return Status::Skipped(reason);
+            }
+            (Err(e), status) => {
+                reporter.event(
+                    pack,
+                    Event::Failed {
+                        error: &e,
+                        fatal: !status,
+                    },
+                );
+                return Status::Failed {
+                    error: e.to_string(),
+                    fatal: !status,
+                };
+            }
+        };
+
+        if task_type == TaskType::Outdated {
+            reporter.event(pack, Event::Done(outcome));
+            return Status::Done(outcome);
         }
 
-        let mut successful = true;
-        let spinner = Spinner::spin(line, SIGN_MARGIN);
-        if let (Err(e), status) = func(pack) {
-            spinner.stop();
-            print_err!(e);
-            successful = status;
-        } else {
-            if pack.build_command.is_some() {
-                echo::inline_message(line, MSG_MARGIN + pos, "building");
-                if let Err(e) = pack.try_build().map_err(|e| Error::build(format!("{}", e))) {
-                    print_err!(e);
+        // Help tags are only generated again when the help files changed.
+        match helptags::generate(pack.path(ctx)) {
+            Ok(warnings) => {
+                for w in &warnings {
+                    reporter.event(pack, Event::Warning(w));
                 }
             }
+            Err(e) => reporter.event(pack, Event::Warning(&format!("helptags: {}", e))),
+        }
 
-            spinner.stop();
-            if successful {
-                echo::character(line, SIGN_MARGIN, '✓', color::Green);
-                echo::inline_message(line, MSG_MARGIN + pos, "done");
+        if pack.build_command.is_some() {
+            reporter.event(pack, Event::Building);
+            if let Err(e) = pack
+                .try_build(ctx)
+                .map_err(|e| Error::build(format!("{}", e)))
+            {
+                reporter.event(
+                    pack,
+                    Event::Failed {
+                        error: &e,
+                        fatal: false,
+                    },
+                );
+                return Status::Failed {
+                    error: e.to_string(),
+                    fatal: false,
+                };
             }
         }
-        successful
+
+        reporter.event(pack, Event::Done(outcome));
+        Status::Done(outcome)
     }
 
-    pub fn run<F>(self, func: F) -> Vec<String>
+    /// Sync the packages with `func`, reporting the progress to `reporter`. Returns the
+    /// results of all packages, the skipped ones included.
+    pub fn run<F>(self, func: F, reporter: Arc<dyn Reporter>) -> Result<Report>
     where
-        F: Fn(&Package) -> (Result<()>, bool) + Send + 'static + Copy,
+        F: Fn(&Context, &Package) -> (Result<Outcome>, bool) + Send + 'static + Copy,
     {
-        if self.packs.is_empty() {
-            die!("No plugins to sync");
+        let start = Instant::now();
+        let mut results = Vec::new();
+        for (pack, reason) in &self.skipped {
+            reporter.event(pack, Event::Skipped(reason));
+            results.push(TaskResult {
+                pack: pack.clone(),
+                status: Status::Skipped(reason.clone()),
+                duration: Duration::default(),
+            });
         }
 
-        let y = match terminal_size() {
-            Err(e) => die!("Fail to get terminal size. {}", e),
-            Ok((_, y)) => y,
-        };
-
-        if y <= 2 {
-            die!("Terminal size too small.");
+        if self.packs.is_empty() {
+            if results.is_empty() {
+                return Err(Error::NoTask);
+            }
+            let report = Report {
+                tasks: results,
+                duration: start.elapsed(),
+            };
+            reporter.end(&report);
+            return Ok(report);
         }
 
-        let quit_notifier = match setup_signal() {
-            Err(e) => die!("Fail to set up signal. {}", e),
-            Ok(r) => r,
-        };
+        let quit_notifier = setup_signal()?;
 
         let threads = self.thread_num;
+        let task_type = self.task_type;
+        let ctx = Arc::new(self.ctx);
 
         let wg = WaitGroup::new();
         let (tx, rx) = bounded::<Option<Package>>(threads);
 
-        let failures = Arc::new(Mutex::new(vec![]));
+        let results = Arc::new(Mutex::new(results));
         let pending = Arc::new(Mutex::new(vec![]));
 
         for _ in 0..threads {
             let rx = rx.clone();
-            let failures = failures.clone();
+            let results = results.clone();
             let pending = pending.clone();
             let wg = wg.clone();
             let quit_notifier = quit_notifier.clone();
+            let reporter = reporter.clone();
+            let ctx = ctx.clone();
             thread::spawn(move || {
                 while let Ok(Some(pack)) = rx.recv() {
                     log::info!("pack {}", &pack.name);
@@ -128,21 +308,28 @@ impl TaskManager {
                     }
 
                     let name = pack.name.clone();
-                    let failures = failures.clone();
+                    let task = pack.clone();
+                    let task_results = results.clone();
+                    let task_reporter = reporter.clone();
+                    let ctx = ctx.clone();
 
                     let (wtx, wrx) = bounded(0);
                     thread::spawn(move || {
-                        let index = echo::line();
-                        if !Self::update(&pack, index, func) {
-                            let mut f = failures.lock().unwrap();
-                            f.push(pack.name);
-                        }
+                        let start = Instant::now();
+                        let status = Self::update(&ctx, task_type, &task, &*task_reporter, func);
+                        let result = TaskResult {
+                            pack: task,
+                            status,
+                            duration: start.elapsed(),
+                        };
+                        record(&task_results, result);
                         let _ = wtx.send(());
                     });
                     select! {
                         recv(wrx) -> _ => {},
                         recv(quit_notifier) -> _ => {
                             log::info!("quit received {}", &name);
+                            record(&results, interrupted(&pack, &*reporter));
                             return;
                         }
                     }
@@ -154,9 +341,9 @@ impl TaskManager {
                 }
             });
         }
-        if !self.packs.is_empty() {
-            println!();
-        }
+        // Sending fails once every worker quit.
+        drop(rx);
+        reporter.begin(self.packs.len());
 
         for pack in self.packs.iter() {
             let _ = tx.send(Some(pack.clone()));
@@ -167,60 +354,135 @@ impl TaskManager {
         }
         wg.wait();
 
-        if !self.packs.is_empty() {
-            println!();
+        // Packages without a result were waiting when the run was interrupted.
+        for pack in self.packs.iter() {
+            let done = results
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|t| t.pack.name == pack.name);
+            if !done {
+                record(&results, interrupted(pack, &*reporter));
+            }
         }
 
-        log::info!("quit");
+        let report = Report {
+            tasks: results.lock().unwrap().clone(),
+            duration: start.elapsed(),
+        };
+        reporter.end(&report);
 
-        helptags();
+        log::info!("quit");
 
         if let TaskType::Install = self.task_type {
             for p in pending.lock().unwrap().iter() {
-                log::info!("delete {:?}", p.path());
-                let _ = fs::remove_dir_all(p.path());
+                log::info!("delete {:?}", p.path(&ctx));
+                let _ = fs::remove_dir_all(p.path(&ctx));
             }
         }
 
-        let failures = failures.lock().unwrap();
-        failures.clone()
+        Ok(report)
     }
 }
 
-fn helptags() {
-    match process::Command::new("vim")
-        .arg("--not-a-term")
-        .arg("-c")
-        .arg("silent! helptags ALL")
-        .stdout(process::Stdio::null())
-        .spawn()
-    {
-        Ok(_) => (),
-        Err(e) => {
-            if let std::io::ErrorKind::NotFound = e.kind() {
-                process::Command::new("nvim")
-                    .arg("--headless")
-                    .arg("-c")
-                    .arg("silent! helptags ALL")
-                    .stdout(process::Stdio::null())
-                    .spawn()
-                    .expect("Error opening nvim");
-            } else {
-                panic!("Somthing happened when calling vim!")
-            }
-        }
+/// Add `result` unless the package already has one, like after it was interrupted.
+fn record(results: &Mutex<Vec<TaskResult>>, result: TaskResult) {
+    let mut results = results.lock().unwrap();
+    if !results.iter().any(|t| t.pack.name == result.pack.name) {
+        results.push(result);
+    }
+}
+
+/// Report `pack` as failed by the interruption of the run.
+fn interrupted(pack: &Package, reporter: &dyn Reporter) -> TaskResult {
+    let error = Error::Interrupted;
+    reporter.event(
+        pack,
+        Event::Failed {
+            error: &error,
+            fatal: true,
+        },
+    );
+    TaskResult {
+        pack: pack.clone(),
+        status: Status::Failed {
+            error: error.to_string(),
+            fatal: true,
+        },
+        duration: Duration::default(),
     }
 }
 
 fn setup_signal() -> io::Result<Receiver<()>> {
     let (s, r) = bounded(10);
-    let signals = Signals::new(&[signal_hook::SIGTERM, signal_hook::SIGINT])?;
+    let signals = Signals::new([signal_hook::SIGTERM, signal_hook::SIGINT])?;
 
     thread::spawn(move || {
-        for _ in signals.forever() {
+        if signals.forever().next().is_some() {
             drop(s);
-            return;
         }
     });
     Ok(r)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_only_skipped() {
+        let mut pack = Package::new("a/one", "default", false);
+        pack.when.editor = Some(String::from("neovim"));
+        let mut manager = TaskManager::new(&Context::new("/vim", None), TaskType::Install, 1);
+        manager.add(pack);
+        let report = manager
+            .run(
+                |_, _| (Ok(Outcome::Installed), true),
+                Arc::new(crate::echo::Plain::default()),
+            )
+            .unwrap();
+        assert_eq!(report.tasks.len(), 1);
+        assert!(matches!(report.tasks[0].status, Status::Skipped(_)));
+
+        let manager = TaskManager::new(&Context::new("/vim", None), TaskType::Install, 1);
+        let empty = manager.run(
+            |_, _| (Ok(Outcome::Installed), true),
+            Arc::new(crate::echo::Plain::default()),
+        );
+        assert!(matches!(empty, Err(Error::NoTask)));
+    }
+
+    #[test]
+    fn report_counts() {
+        let task = |name: &str, status| TaskResult {
+            pack: Package::new(name, "default", false),
+            status,
+            duration: Duration::default(),
+        };
+        let report = Report {
+            tasks: vec![
+                task("a", Status::Done(Outcome::Installed)),
+                task("b", Status::Skipped("local".to_string())),
+                task(
+                    "c",
+                    Status::Failed {
+                        error: "network".to_string(),
+                        fatal: true,
+                    },
+                ),
+                task(
+                    "d",
+                    Status::Failed {
+                        error: "build".to_string(),
+                        fatal: false,
+                    },
+                ),
+            ],
+            duration: Duration::default(),
+        };
+        assert_eq!(report.done(), 1);
+        assert_eq!(report.failed(), 2);
+        let failures: Vec<_> = report.failures().into_iter().map(|p| p.name).collect();
+        assert_eq!(failures, vec!["c", "d"]);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::context::Context;
+use crate::git;
+use crate::package::{self, Package};
+use crate::task::{Outcome, Report, Reporter, TaskManager, TaskType};
+use crate::{Error, Result};
+
+use std::sync::Arc;
+
+/// Update `plugins`, or every package of the packfile except the ones matching `skip` if
+/// no names are given. Packages which failed keep their loaders and configs in the
+/// generated plugin file. Returns the results of the packages.
+pub fn update_plugins(
+    ctx: &Context,
+    plugins: &[String],
+    threads: usize,
+    skip: &[String],
+    reporter: Arc<dyn Reporter>,
+) -> Result<Report> {
+    let mut packs = package::fetch(ctx)?;
+    package::move_start_packages(ctx, &packs)?;
+
+    let mut manager = TaskManager::new(ctx, TaskType::Update, threads);
+    if plugins.is_empty() {
+        for pack in &packs {
+            if skip.iter().any(|x| pack.name.contains(x)) {
+                manager.skip(pack.clone(), "excluded by --skip");
+                continue;
+            }
+            manager.add(pack.clone());
+        }
+    } else {
+        for pack in packs.iter().filter(|x| plugins.contains(&x.name)) {
+            manager.add(pack.clone());
+        }
+    }
+
+    let report = manager.run(update_plugin, reporter)?;
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    package::update_pack_plugin(ctx, &packs)?;
+
+    Ok(report)
+}
+
+/// Check whether `plugins`, or every package of the packfile if no names are given, have
+/// updates, without changing them. Returns the results of the packages, with the outdated
+/// ones done as `Outcome::Outdated`.
+pub fn outdated_plugins(
+    ctx: &Context,
+    plugins: &[String],
+    threads: usize,
+    reporter: Arc<dyn Reporter>,
+) -> Result<Report> {
+    let packs = package::fetch(ctx)?;
+
+    let mut manager = TaskManager::new(ctx, TaskType::Outdated, threads);
+    for pack in packs
+        .into_iter()
+        .filter(|x| plugins.is_empty() || plugins.contains(&x.name))
+    {
+        manager.add(pack);
+    }
+    manager.run(check_plugin, reporter)
+}
+
+fn check_plugin(ctx: &Context, pack: &Package) -> (Result<Outcome>, bool) {
+    let path = pack.path(ctx);
+    let res = if !path.is_dir() {
+        Err(Error::PluginNotInstalled)
+    } else if pack.local {
+        Err(Error::SkipLocal)
+    } else {
+        match git::is_outdated(&pack.name, &path) {
+            Ok(true) => Ok(Outcome::Outdated),
+            Ok(false) => Ok(Outcome::Unchanged),
+            Err(e) => Err(e),
+        }
+    };
+    // Nothing is changed, so a failure never leaves the package unusable.
+    (res, true)
+}
+
+fn update_plugin(ctx: &Context, pack: &Package) -> (Result<Outcome>, bool) {
+    let res = do_update(ctx, pack);
+    let status = match res {
+        Err(Error::SkipLocal) | Err(Error::Git(_)) => true,
+        Err(_) => false,
+        _ => true,
+    };
+    (res, status)
+}
+
+fn do_update(ctx: &Context, pack: &Package) -> Result<Outcome> {
+    let path = pack.path(ctx);
+    if !path.is_dir() {
+        Err(Error::PluginNotInstalled)
+    } else if pack.local {
+        Err(Error::SkipLocal)
+    } else {
+        match git::update(&pack.name, &path)? {
+            true => Ok(Outcome::Updated),
+            false => Ok(Outcome::Unchanged),
+        }
+    }
+}
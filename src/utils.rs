@@ -1,54 +1,16 @@
-use crate::echo;
 use crate::{Error, Result};
 
 use std::env;
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::process;
-use std::sync::mpsc::{channel, Sender};
-use std::thread;
-use std::time;
-use termion::color;
 use walkdir::WalkDir;
+use yaml_rust::Yaml;
 
-const SPINNER_CHARS: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 const DEFAULT_EDITOR: &str = "vi";
 
-macro_rules! die {
-    ($($arg:tt)*) => ({
-        use std::io::Write;
-        (writeln!(&mut ::std::io::stderr(), $($arg)*)).expect("stderr");
-        ::std::process::exit(1)
-    })
-}
-
-pub struct Spinner {
-    tx: Sender<bool>,
-    handle: thread::JoinHandle<()>,
-}
-
-impl Spinner {
-    pub fn spin(x: u16, y: u16) -> Spinner {
-        let (tx, rx) = channel();
-        let handle = thread::spawn(move || {
-            for &c in SPINNER_CHARS.iter().cycle() {
-                if rx.try_recv().is_ok() {
-                    break;
-                }
-
-                echo::character(x, y, c, color::Reset);
-                thread::sleep(time::Duration::from_millis(100));
-            }
-        });
-        Spinner { tx, handle }
-    }
-
-    pub fn stop(self) {
-        self.tx.send(true).unwrap();
-        self.handle.join().unwrap();
-    }
-}
-
 pub fn copy_directory<P: AsRef<Path>>(src: P, dst: P) -> Result<()> {
     let wd = WalkDir::new(&src);
     for entry in wd {
@@ -65,6 +27,58 @@ pub fn copy_directory<P: AsRef<Path>>(src: P, dst: P) -> Result<()> {
     Ok(())
 }
 
+/// Replace the content of `path` by writing a temporary file next to it and renaming it,
+/// so that readers never see a partially written file.
+pub fn write_atomic<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<()> {
+    // A symlink, like to a packfile kept with other dotfiles, is written through.
+    let path = fs::canonicalize(path.as_ref()).unwrap_or_else(|_| path.as_ref().to_path_buf());
+    let path = path.as_path();
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file name"))?;
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name, process::id()));
+
+    let res = File::create(&tmp).and_then(|mut f| {
+        f.write_all(data)?;
+        f.sync_all()
+    });
+    if let Err(e) = res.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(Error::Io(e));
+    }
+    Ok(())
+}
+
+/// Advisory lock held until dropped.
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Take an exclusive lock on `path`, waiting for other holders. `on_wait` is called
+    /// once if the lock is held by someone else.
+    pub fn acquire<P: AsRef<Path>, F: FnOnce()>(path: P, on_wait: F) -> Result<FileLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        let fd = file.as_raw_fd();
+        if unsafe { libc::flock(fd, libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EWOULDBLOCK) {
+                return Err(Error::Io(err));
+            }
+            on_wait();
+            if unsafe { libc::flock(fd, libc::LOCK_EX) } != 0 {
+                return Err(Error::Io(io::Error::last_os_error()));
+            }
+        }
+        Ok(FileLock { _file: file })
+    }
+}
+
 fn get_editor() -> Option<String> {
     let term = env::var("TERM");
     if term.map(|t| t == "dumb").unwrap_or(true) {
@@ -88,3 +102,66 @@ pub fn open_editor<P: AsRef<Path>>(path: P) -> Result<()> {
         .wait()?;
     Ok(())
 }
+
+/// Items of a packfile field which takes a string or a list of strings. A missing or empty
+/// field has none.
+pub fn string_list(doc: &Yaml) -> Result<Vec<String>> {
+    if let Some(s) = doc.as_str() {
+        return Ok(vec![s.to_string()]);
+    }
+    match doc.as_vec() {
+        Some(f) => {
+            let mut items = Vec::with_capacity(f.len());
+            for e in f {
+                items.push(e.as_str().map(|s| s.to_string()).ok_or(Error::Format)?);
+            }
+            Ok(items)
+        }
+        None if doc.is_badvalue() || doc.is_null() => Ok(vec![]),
+        None => Err(Error::Format),
+    }
+}
+
+/// Quote a string as a vim script string literal.
+pub fn vim_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Quote a string as a Lua string literal.
+pub fn lua_string(s: &str) -> String {
+    format!(
+        "'{}'",
+        s.replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Quote a string as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    let end = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..end].to_vec()).ok()
+}